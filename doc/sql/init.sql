@@ -1,3 +1,6 @@
+-- Schema of a new database. Databases created from an older version of this
+-- file are brought up to date with doc/sql/migrations, applied in order.
+
 CREATE TABLE public.codmap
 (
     id       uuid    DEFAULT gen_random_uuid() NOT NULL,
@@ -1103,3 +1106,234 @@ ALTER TABLE ONLY public.codmap
 
 CREATE INDEX codmap_active_false_index ON public.codmap USING btree (active) WHERE (active = false);
 CREATE INDEX codmap_active_true_index ON public.codmap USING btree (active) WHERE (active = true);
+CREATE INDEX codmap_seq_tag_index ON public.codmap USING btree (seq, tag);
+
+
+CREATE TABLE public.player_stats
+(
+    guid        character varying(64)    NOT NULL,
+    name        character varying(64)    NOT NULL,
+    kills       bigint DEFAULT 0         NOT NULL,
+    deaths      bigint DEFAULT 0         NOT NULL,
+    headshots   bigint DEFAULT 0         NOT NULL,
+    suicides    bigint DEFAULT 0         NOT NULL,
+    team_kills  bigint DEFAULT 0         NOT NULL,
+    damage      bigint DEFAULT 0         NOT NULL,
+    time_played bigint DEFAULT 0         NOT NULL,
+    first_seen  timestamp with time zone NOT NULL,
+    last_seen   timestamp with time zone NOT NULL
+);
+
+-- Every log line already counted, so that reading a log twice is harmless
+CREATE TABLE public.player_stats_line
+(
+    server_id   character varying(32)    NOT NULL,
+    log_file    character varying(64)    NOT NULL,
+    log_offset  bigint                   NOT NULL,
+    checksum    character(32)            NOT NULL,
+    recorded_at timestamp with time zone NOT NULL
+);
+
+ALTER TABLE ONLY public.player_stats
+    ADD CONSTRAINT player_stats_guid_pk PRIMARY KEY (guid);
+
+ALTER TABLE ONLY public.player_stats_line
+    ADD CONSTRAINT player_stats_line_pk PRIMARY KEY (server_id, log_file, log_offset, checksum);
+
+CREATE INDEX player_stats_line_recorded_at_index ON public.player_stats_line USING btree (recorded_at);
+
+
+-- One row for every map played, identified by its InitGame line. The map is
+-- linked to the catalog when it is there.
+CREATE TABLE public.game_match
+(
+    id           uuid DEFAULT gen_random_uuid() NOT NULL,
+    server_id    character varying(32)          NOT NULL,
+    map          character varying(128)         NOT NULL,
+    codmap_id    uuid,
+    gametype     character varying(32)          NOT NULL,
+    started_at   timestamp with time zone       NOT NULL,
+    ended_at     timestamp with time zone       NOT NULL,
+    duration     bigint                         NOT NULL,
+    allies_kills bigint DEFAULT 0               NOT NULL,
+    axis_kills   bigint DEFAULT 0               NOT NULL,
+    winner       character varying(64),
+    log_file     character varying(64)          NOT NULL,
+    log_offset   bigint                         NOT NULL,
+    checksum     character(32)                  NOT NULL
+);
+
+CREATE TABLE public.match_player
+(
+    match_id    uuid                  NOT NULL,
+    guid        character varying(64) NOT NULL,
+    name        character varying(64) NOT NULL,
+    team        character varying(16) NOT NULL,
+    score       bigint DEFAULT 0      NOT NULL,
+    won         boolean,
+    kills       bigint DEFAULT 0      NOT NULL,
+    deaths      bigint DEFAULT 0      NOT NULL,
+    headshots   bigint DEFAULT 0      NOT NULL,
+    suicides    bigint DEFAULT 0      NOT NULL,
+    team_kills  bigint DEFAULT 0      NOT NULL,
+    damage      bigint DEFAULT 0      NOT NULL,
+    time_played bigint DEFAULT 0      NOT NULL
+);
+
+ALTER TABLE ONLY public.game_match
+    ADD CONSTRAINT game_match_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.game_match
+    ADD CONSTRAINT game_match_server_id_log_file_log_offset_checksum_k UNIQUE (server_id, log_file, log_offset, checksum);
+
+ALTER TABLE ONLY public.game_match
+    ADD CONSTRAINT game_match_codmap_id_fk FOREIGN KEY (codmap_id) REFERENCES public.codmap (id) ON DELETE SET NULL;
+
+ALTER TABLE ONLY public.match_player
+    ADD CONSTRAINT match_player_match_id_guid_pk PRIMARY KEY (match_id, guid);
+
+ALTER TABLE ONLY public.match_player
+    ADD CONSTRAINT match_player_match_id_fk FOREIGN KEY (match_id) REFERENCES public.game_match (id) ON DELETE CASCADE;
+
+CREATE INDEX game_match_started_at_index ON public.game_match USING btree (started_at);
+CREATE INDEX game_match_server_id_started_at_index ON public.game_match USING btree (server_id, started_at);
+CREATE INDEX game_match_gametype_started_at_index ON public.game_match USING btree (gametype, started_at);
+CREATE INDEX game_match_map_index ON public.game_match USING btree (map);
+CREATE INDEX match_player_guid_index ON public.match_player USING btree (guid);
+
+
+-- Gametypes g_gametype can be set to, mods add their own
+CREATE TABLE public.gametype
+(
+    tag         character varying(32)       NOT NULL,
+    name        character varying(128)      NOT NULL,
+    description text,
+    team_based  boolean DEFAULT true        NOT NULL,
+    source_mod  character varying(64)
+);
+
+INSERT INTO public.gametype (tag, name, description, team_based, source_mod)
+VALUES ('dm', 'Free-for-all', 'Every player for themselves, the most kills wins', false, NULL),
+       ('war', 'Team Deathmatch', 'Two teams, the most kills wins', true, NULL),
+       ('dom', 'Domination', 'Capture and hold three flags', true, NULL),
+       ('sd', 'Search and Destroy', 'One life per round, attackers plant the bomb and defenders defuse it', true, NULL),
+       ('koth', 'Headquarters', 'Capture and defend the headquarters', true, NULL),
+       ('sab', 'Sabotage', 'Carry the bomb to the enemy base', true, NULL);
+
+ALTER TABLE ONLY public.gametype
+    ADD CONSTRAINT gametype_tag_pk PRIMARY KEY (tag);
+
+
+-- Gametypes each map has spawns and objectives for. Maps without any row,
+-- the stock ones included, are not restricted: only the exceptions are
+-- listed, so gametypes added later stay playable on them.
+CREATE TABLE public.codmap_gametype
+(
+    codmap_id uuid                  NOT NULL,
+    gametype  character varying(32) NOT NULL
+);
+
+ALTER TABLE ONLY public.codmap_gametype
+    ADD CONSTRAINT codmap_gametype_pk PRIMARY KEY (codmap_id, gametype);
+
+ALTER TABLE ONLY public.codmap_gametype
+    ADD CONSTRAINT codmap_gametype_codmap_id_fk FOREIGN KEY (codmap_id) REFERENCES public.codmap (id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY public.codmap_gametype
+    ADD CONSTRAINT codmap_gametype_gametype_fk FOREIGN KEY (gametype) REFERENCES public.gametype (tag) ON DELETE CASCADE;
+
+CREATE INDEX codmap_gametype_gametype_index ON public.codmap_gametype USING btree (gametype);
+
+
+CREATE TABLE public.rotation
+(
+    id   uuid DEFAULT gen_random_uuid() NOT NULL,
+    name character varying(128)         NOT NULL
+);
+
+CREATE TABLE public.rotation_entry
+(
+    id          uuid DEFAULT gen_random_uuid() NOT NULL,
+    rotation_id uuid                           NOT NULL,
+    seq         integer                        NOT NULL,
+    gametype    character varying(32)          NOT NULL,
+    codmap_id   uuid                           NOT NULL
+);
+
+ALTER TABLE ONLY public.rotation
+    ADD CONSTRAINT rotation_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.rotation
+    ADD CONSTRAINT rotation_name_k UNIQUE (name);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_rotation_id_seq_k UNIQUE (rotation_id, seq);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_rotation_id_fk FOREIGN KEY (rotation_id) REFERENCES public.rotation (id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_codmap_id_fk FOREIGN KEY (codmap_id) REFERENCES public.codmap (id);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_gametype_fk FOREIGN KEY (gametype) REFERENCES public.gametype (tag);
+
+
+CREATE TABLE public.game_server
+(
+    id            character varying(32)               NOT NULL,
+    name          character varying(128)              NOT NULL,
+    host          character varying(255)              NOT NULL,
+    port          integer DEFAULT 28960               NOT NULL,
+    game          character varying(16) DEFAULT 'cod4' NOT NULL,
+    rcon_password character varying(128)              NOT NULL,
+    log_path      character varying(1024),
+    active        boolean DEFAULT true                NOT NULL
+);
+
+ALTER TABLE ONLY public.game_server
+    ADD CONSTRAINT game_server_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.game_server
+    ADD CONSTRAINT game_server_port_check CHECK (port > 0 AND port < 65536);
+
+
+-- One row for every health check of a game server
+CREATE TABLE public.server_health
+(
+    server_id   character varying(32)    NOT NULL,
+    checked_at  timestamp with time zone NOT NULL,
+    online      boolean                  NOT NULL,
+    latency     integer,
+    players     integer,
+    max_players integer,
+    map         character varying(128),
+    gametype    character varying(32)
+);
+
+ALTER TABLE ONLY public.server_health
+    ADD CONSTRAINT server_health_server_id_checked_at_pk PRIMARY KEY (server_id, checked_at);
+
+CREATE INDEX server_health_checked_at_index ON public.server_health USING btree (checked_at);
+
+
+-- Every command sent through the console endpoint, denied ones included
+CREATE TABLE public.console_log
+(
+    id           uuid DEFAULT gen_random_uuid() NOT NULL,
+    server_id    character varying(32)          NOT NULL,
+    command      character varying(1024)        NOT NULL,
+    allowed      boolean                        NOT NULL,
+    reply        text,
+    error        text,
+    remote_addr  character varying(64),
+    requested_at timestamp with time zone       NOT NULL
+);
+
+ALTER TABLE ONLY public.console_log
+    ADD CONSTRAINT console_log_id_pk PRIMARY KEY (id);
+
+CREATE INDEX console_log_requested_at_index ON public.console_log USING btree (requested_at);
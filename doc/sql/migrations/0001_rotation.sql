@@ -0,0 +1,34 @@
+-- Map rotations
+
+CREATE TABLE public.rotation
+(
+    id   uuid DEFAULT gen_random_uuid() NOT NULL,
+    name character varying(128)         NOT NULL
+);
+
+CREATE TABLE public.rotation_entry
+(
+    id          uuid DEFAULT gen_random_uuid() NOT NULL,
+    rotation_id uuid                           NOT NULL,
+    seq         integer                        NOT NULL,
+    gametype    character varying(32)          NOT NULL,
+    codmap_id   uuid                           NOT NULL
+);
+
+ALTER TABLE ONLY public.rotation
+    ADD CONSTRAINT rotation_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.rotation
+    ADD CONSTRAINT rotation_name_k UNIQUE (name);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_rotation_id_seq_k UNIQUE (rotation_id, seq);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_rotation_id_fk FOREIGN KEY (rotation_id) REFERENCES public.rotation (id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_codmap_id_fk FOREIGN KEY (codmap_id) REFERENCES public.codmap (id);
@@ -0,0 +1,17 @@
+-- Fleet of game servers
+
+CREATE TABLE public.game_server
+(
+    id            character varying(32)  NOT NULL,
+    name          character varying(128) NOT NULL,
+    host          character varying(255) NOT NULL,
+    port          integer DEFAULT 28960  NOT NULL,
+    rcon_password character varying(128) NOT NULL,
+    active        boolean DEFAULT true   NOT NULL
+);
+
+ALTER TABLE ONLY public.game_server
+    ADD CONSTRAINT game_server_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.game_server
+    ADD CONSTRAINT game_server_port_check CHECK (port > 0 AND port < 65536);
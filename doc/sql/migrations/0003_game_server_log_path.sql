@@ -0,0 +1,4 @@
+-- Game log of each server
+
+ALTER TABLE public.game_server
+    ADD COLUMN log_path character varying(1024);
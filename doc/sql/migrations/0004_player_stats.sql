@@ -0,0 +1,34 @@
+-- Per-player statistics
+
+CREATE TABLE public.player_stats
+(
+    guid        character varying(64)    NOT NULL,
+    name        character varying(64)    NOT NULL,
+    kills       bigint DEFAULT 0         NOT NULL,
+    deaths      bigint DEFAULT 0         NOT NULL,
+    headshots   bigint DEFAULT 0         NOT NULL,
+    suicides    bigint DEFAULT 0         NOT NULL,
+    team_kills  bigint DEFAULT 0         NOT NULL,
+    damage      bigint DEFAULT 0         NOT NULL,
+    time_played bigint DEFAULT 0         NOT NULL,
+    first_seen  timestamp with time zone NOT NULL,
+    last_seen   timestamp with time zone NOT NULL
+);
+
+-- Every log line already counted, so that reading a log twice is harmless
+CREATE TABLE public.player_stats_line
+(
+    server_id   character varying(32)    NOT NULL,
+    log_file    character varying(64)    NOT NULL,
+    log_offset  bigint                   NOT NULL,
+    checksum    character(32)            NOT NULL,
+    recorded_at timestamp with time zone NOT NULL
+);
+
+ALTER TABLE ONLY public.player_stats
+    ADD CONSTRAINT player_stats_guid_pk PRIMARY KEY (guid);
+
+ALTER TABLE ONLY public.player_stats_line
+    ADD CONSTRAINT player_stats_line_pk PRIMARY KEY (server_id, log_file, log_offset, checksum);
+
+CREATE INDEX player_stats_line_recorded_at_index ON public.player_stats_line USING btree (recorded_at);
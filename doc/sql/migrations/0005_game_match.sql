@@ -0,0 +1,58 @@
+-- Match history
+
+-- One row for every map played, identified by its InitGame line. The map is
+-- linked to the catalog when it is there.
+CREATE TABLE public.game_match
+(
+    id           uuid DEFAULT gen_random_uuid() NOT NULL,
+    server_id    character varying(32)          NOT NULL,
+    map          character varying(128)         NOT NULL,
+    codmap_id    uuid,
+    gametype     character varying(32)          NOT NULL,
+    started_at   timestamp with time zone       NOT NULL,
+    ended_at     timestamp with time zone       NOT NULL,
+    duration     bigint                         NOT NULL,
+    allies_kills bigint DEFAULT 0               NOT NULL,
+    axis_kills   bigint DEFAULT 0               NOT NULL,
+    winner       character varying(64),
+    log_file     character varying(64)          NOT NULL,
+    log_offset   bigint                         NOT NULL,
+    checksum     character(32)                  NOT NULL
+);
+
+CREATE TABLE public.match_player
+(
+    match_id    uuid                  NOT NULL,
+    guid        character varying(64) NOT NULL,
+    name        character varying(64) NOT NULL,
+    team        character varying(16) NOT NULL,
+    score       bigint DEFAULT 0      NOT NULL,
+    won         boolean,
+    kills       bigint DEFAULT 0      NOT NULL,
+    deaths      bigint DEFAULT 0      NOT NULL,
+    headshots   bigint DEFAULT 0      NOT NULL,
+    suicides    bigint DEFAULT 0      NOT NULL,
+    team_kills  bigint DEFAULT 0      NOT NULL,
+    damage      bigint DEFAULT 0      NOT NULL,
+    time_played bigint DEFAULT 0      NOT NULL
+);
+
+ALTER TABLE ONLY public.game_match
+    ADD CONSTRAINT game_match_id_pk PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.game_match
+    ADD CONSTRAINT game_match_server_id_log_file_log_offset_checksum_k UNIQUE (server_id, log_file, log_offset, checksum);
+
+ALTER TABLE ONLY public.game_match
+    ADD CONSTRAINT game_match_codmap_id_fk FOREIGN KEY (codmap_id) REFERENCES public.codmap (id) ON DELETE SET NULL;
+
+ALTER TABLE ONLY public.match_player
+    ADD CONSTRAINT match_player_match_id_guid_pk PRIMARY KEY (match_id, guid);
+
+ALTER TABLE ONLY public.match_player
+    ADD CONSTRAINT match_player_match_id_fk FOREIGN KEY (match_id) REFERENCES public.game_match (id) ON DELETE CASCADE;
+
+CREATE INDEX game_match_started_at_index ON public.game_match USING btree (started_at);
+CREATE INDEX game_match_server_id_started_at_index ON public.game_match USING btree (server_id, started_at);
+CREATE INDEX game_match_map_index ON public.game_match USING btree (map);
+CREATE INDEX match_player_guid_index ON public.match_player USING btree (guid);
@@ -0,0 +1,3 @@
+-- Leaderboards by gametype
+
+CREATE INDEX game_match_gametype_started_at_index ON public.game_match USING btree (gametype, started_at);
@@ -0,0 +1,19 @@
+-- Health history
+
+-- One row for every health check of a game server
+CREATE TABLE public.server_health
+(
+    server_id   character varying(32)    NOT NULL,
+    checked_at  timestamp with time zone NOT NULL,
+    online      boolean                  NOT NULL,
+    latency     integer,
+    players     integer,
+    max_players integer,
+    map         character varying(128),
+    gametype    character varying(32)
+);
+
+ALTER TABLE ONLY public.server_health
+    ADD CONSTRAINT server_health_server_id_checked_at_pk PRIMARY KEY (server_id, checked_at);
+
+CREATE INDEX server_health_checked_at_index ON public.server_health USING btree (checked_at);
@@ -0,0 +1,19 @@
+-- Console audit log
+
+-- Every command sent through the console endpoint, denied ones included
+CREATE TABLE public.console_log
+(
+    id           uuid DEFAULT gen_random_uuid() NOT NULL,
+    server_id    character varying(32)          NOT NULL,
+    command      character varying(1024)        NOT NULL,
+    allowed      boolean                        NOT NULL,
+    reply        text,
+    error        text,
+    remote_addr  character varying(64),
+    requested_at timestamp with time zone       NOT NULL
+);
+
+ALTER TABLE ONLY public.console_log
+    ADD CONSTRAINT console_log_id_pk PRIMARY KEY (id);
+
+CREATE INDEX console_log_requested_at_index ON public.console_log USING btree (requested_at);
@@ -0,0 +1,4 @@
+-- Game title of each server
+
+ALTER TABLE public.game_server
+    ADD COLUMN game character varying(16) DEFAULT 'cod4' NOT NULL;
@@ -0,0 +1,25 @@
+-- Gametype catalog
+
+-- Gametypes g_gametype can be set to, mods add their own
+CREATE TABLE public.gametype
+(
+    tag         character varying(32)       NOT NULL,
+    name        character varying(128)      NOT NULL,
+    description text,
+    team_based  boolean DEFAULT true        NOT NULL,
+    source_mod  character varying(64)
+);
+
+INSERT INTO public.gametype (tag, name, description, team_based, source_mod)
+VALUES ('dm', 'Free-for-all', 'Every player for themselves, the most kills wins', false, NULL),
+       ('war', 'Team Deathmatch', 'Two teams, the most kills wins', true, NULL),
+       ('dom', 'Domination', 'Capture and hold three flags', true, NULL),
+       ('sd', 'Search and Destroy', 'One life per round, attackers plant the bomb and defenders defuse it', true, NULL),
+       ('koth', 'Headquarters', 'Capture and defend the headquarters', true, NULL),
+       ('sab', 'Sabotage', 'Carry the bomb to the enemy base', true, NULL);
+
+ALTER TABLE ONLY public.gametype
+    ADD CONSTRAINT gametype_tag_pk PRIMARY KEY (tag);
+
+ALTER TABLE ONLY public.rotation_entry
+    ADD CONSTRAINT rotation_entry_gametype_fk FOREIGN KEY (gametype) REFERENCES public.gametype (tag);
@@ -0,0 +1,21 @@
+-- Map and gametype compatibility
+
+-- Gametypes each map has spawns and objectives for. Maps without any row,
+-- the stock ones included, are not restricted: only the exceptions are
+-- listed, so gametypes added later stay playable on them.
+CREATE TABLE public.codmap_gametype
+(
+    codmap_id uuid                  NOT NULL,
+    gametype  character varying(32) NOT NULL
+);
+
+ALTER TABLE ONLY public.codmap_gametype
+    ADD CONSTRAINT codmap_gametype_pk PRIMARY KEY (codmap_id, gametype);
+
+ALTER TABLE ONLY public.codmap_gametype
+    ADD CONSTRAINT codmap_gametype_codmap_id_fk FOREIGN KEY (codmap_id) REFERENCES public.codmap (id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY public.codmap_gametype
+    ADD CONSTRAINT codmap_gametype_gametype_fk FOREIGN KEY (gametype) REFERENCES public.gametype (tag) ON DELETE CASCADE;
+
+CREATE INDEX codmap_gametype_gametype_index ON public.codmap_gametype USING btree (gametype);
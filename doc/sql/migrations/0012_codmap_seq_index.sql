@@ -0,0 +1,3 @@
+-- Maps ordered by seq
+
+CREATE INDEX codmap_seq_tag_index ON public.codmap USING btree (seq, tag);
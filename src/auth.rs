@@ -0,0 +1,95 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::error::ApiError;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{Error, ResponseError};
+
+// Token expected as a bearer in the Authorization header of every admin
+// request. Without one configured the admin API refuses everything.
+#[derive(Clone, Debug, Default)]
+pub struct AdminToken(Option<String>);
+
+impl AdminToken {
+    pub fn new(token: Option<String>) -> Self {
+        Self(token.filter(|token| !token.is_empty()))
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    // Every byte is compared, so the time taken tells nothing about how much
+    // of the candidate was right
+    fn accepts(&self, candidate: &str) -> bool {
+        let Some(token) = &self.0 else {
+            return false;
+        };
+        token.len() == candidate.len()
+            && token
+                .bytes()
+                .zip(candidate.bytes())
+                .fold(0, |difference, (a, b)| difference | (a ^ b))
+                == 0
+    }
+}
+
+pub async fn require_admin(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let candidate = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let authorized = match (req.app_data::<Data<AdminToken>>(), candidate) {
+        (Some(token), Some(candidate)) => token.accepts(candidate.trim()),
+        _ => false,
+    };
+    if !authorized {
+        let mut response =
+            ApiError::Unauthorized("Missing or invalid admin token".to_string()).error_response();
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts() {
+        let token = AdminToken::new(Some("s3cret".to_string()));
+        assert!(token.accepts("s3cret"));
+        assert!(!token.accepts("s3cre"));
+        assert!(!token.accepts("s3cret!"));
+        assert!(!token.accepts(""));
+        assert!(!AdminToken::new(None).accepts(""));
+        assert!(!AdminToken::new(Some(String::new())).accepts(""));
+    }
+}
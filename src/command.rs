@@ -0,0 +1,159 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::rcon::RconError;
+use std::fmt::{Display, Formatter};
+
+enum Argument {
+    Word(String),
+    Quoted(String),
+    Number(i64),
+}
+
+// An RCON command whose arguments cannot change its meaning. The engine
+// splits a line into several commands on semicolons and line breaks, and
+// into arguments on whitespace and quotes; arguments able to do either are
+// refused before anything is sent.
+pub struct RconCommand {
+    name: String,
+    args: Vec<Argument>,
+}
+
+impl Display for RconCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        for arg in &self.args {
+            match arg {
+                Argument::Word(word) => write!(f, " {}", word)?,
+                Argument::Quoted(text) => write!(f, " \"{}\"", text)?,
+                Argument::Number(number) => write!(f, " {}", number)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RconCommand {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: name.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    // Reading a dvar is done by sending its bare name
+    pub fn dvar(name: &str) -> Result<Self, RconError> {
+        Ok(Self {
+            name: Self::check_word(name)?.to_string(),
+            args: Vec::new(),
+        })
+    }
+
+    // Map names, dvar names, gametype tags...
+    pub fn word(mut self, word: &str) -> Result<Self, RconError> {
+        let word = Self::check_word(word)?;
+        self.args.push(Argument::Word(word.to_string()));
+        Ok(self)
+    }
+
+    // Free text. Between quotes semicolons are harmless, quotes and control
+    // characters are not.
+    pub fn quoted(mut self, text: &str) -> Result<Self, RconError> {
+        if let Some(c) = text.chars().find(|c| *c == '"' || c.is_control()) {
+            return Err(RconError::InvalidArgument(format!(
+                "{:?} is not allowed in {:?}",
+                c, text
+            )));
+        }
+        self.args.push(Argument::Quoted(text.to_string()));
+        Ok(self)
+    }
+
+    pub fn number(mut self, number: impl Into<i64>) -> Self {
+        self.args.push(Argument::Number(number.into()));
+        self
+    }
+
+    fn check_word(word: &str) -> Result<&str, RconError> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.';
+        if word.is_empty() || !word.chars().all(valid) {
+            return Err(RconError::InvalidArgument(format!(
+                "{:?} is not a valid word",
+                word
+            )));
+        }
+        Ok(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_command() -> anyhow::Result<()> {
+        let command = RconCommand::new("map").word("mp_crash")?;
+        assert_eq!(command.to_string(), "map mp_crash");
+
+        let command = RconCommand::new("tell").number(3u8).quoted("push B; now")?;
+        assert_eq!(command.to_string(), "tell 3 \"push B; now\"");
+
+        let command = RconCommand::new("set")
+            .word("sv_hostname")?
+            .quoted("^1OLG ^7Public")?;
+        assert_eq!(command.to_string(), "set sv_hostname \"^1OLG ^7Public\"");
+
+        assert_eq!(RconCommand::dvar("g_gametype")?.to_string(), "g_gametype");
+        Ok(())
+    }
+
+    #[test]
+    fn test_injection_refused() {
+        for word in [
+            "mp_crash; rcon_password x",
+            "mp_crash;quit",
+            "mp_crash\nquit",
+            "mp_crash\r",
+            "mp crash",
+            "\"mp_crash\"",
+            "//mp_crash",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    RconCommand::new("map").word(word),
+                    Err(RconError::InvalidArgument(_))
+                ),
+                "{:?} accepted",
+                word
+            );
+        }
+
+        for text in ["hi\" ; quit", "hi\nquit", "hi\rquit", "hi\0"] {
+            assert!(
+                matches!(
+                    RconCommand::new("say").quoted(text),
+                    Err(RconError::InvalidArgument(_))
+                ),
+                "{:?} accepted",
+                text
+            );
+        }
+
+        assert!(RconCommand::dvar("sv_hostname;quit").is_err());
+    }
+}
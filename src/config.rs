@@ -15,15 +15,35 @@
  *
  */
 
-use std::env;
+use crate::auth::AdminToken;
+use crate::fleet::ServerDefinition;
+use crate::game::Game;
+use crate::models::{ConsolePolicy, DvarAllowlist};
+use std::{env, fs};
 use tracing::{info, instrument};
 
 pub(crate) struct Configuration {
     pub(crate) http_bind_host: String,
     pub(crate) http_bind_port: u16,
+    pub(crate) admin_token: AdminToken,
     pub(crate) server_host: String,
     pub(crate) server_port: u16,
+    pub(crate) server_game: Game,
     pub(crate) server_rconpassword: String,
+    pub(crate) server_rcon_timeout: u64,
+    pub(crate) server_rcon_retries: u32,
+    pub(crate) server_rcon_quiet_period: u64,
+    pub(crate) server_rcon_max_response_size: usize,
+    pub(crate) server_rcon_min_interval: u64,
+    pub(crate) server_log_path: Option<String>,
+    pub(crate) server_log_poll_interval: u64,
+    pub(crate) server_log_replay: bool,
+    pub(crate) server_log_retention_days: i64,
+    pub(crate) server_health_interval: u64,
+    pub(crate) server_health_retention_days: i64,
+    pub(crate) server_dvars: DvarAllowlist,
+    pub(crate) server_console: ConsolePolicy,
+    pub(crate) servers: Vec<ServerDefinition>,
     pub(crate) db_host: String,
     pub(crate) db_port: u16,
     pub(crate) db_user: String,
@@ -35,17 +55,70 @@ pub(crate) struct Configuration {
 pub async fn parse() -> anyhow::Result<Configuration> {
     info!("Loading configuration");
 
+    let server_dvars = match env::var("SERVER_DVARS_FILE") {
+        Ok(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        Err(_) => DvarAllowlist::default(),
+    };
+
+    let server_console = match env::var("SERVER_CONSOLE_FILE") {
+        Ok(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        Err(_) => ConsolePolicy::default(),
+    };
+
+    let servers = match env::var("SERVERS_FILE") {
+        Ok(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        Err(_) => Vec::new(),
+    };
+
     Ok(Configuration {
         http_bind_host: env::var("HTTP_BIND_HOST").unwrap_or_else(|_| "::".to_string()),
         http_bind_port: env::var("HTTP_BIND_PORT")
             .unwrap_or_else(|_| "7000".to_string())
             .parse::<u16>()?,
+        admin_token: AdminToken::new(env::var("ADMIN_TOKEN").ok()),
         server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
         server_port: env::var("SERVER_PORT")
             .unwrap_or_else(|_| "28960".to_string())
             .parse::<u16>()?,
+        server_game: env::var("SERVER_GAME")
+            .unwrap_or_else(|_| "cod4".to_string())
+            .parse::<Game>()?,
         server_rconpassword: env::var("SERVER_RCONPASSWORD")
             .unwrap_or_else(|_| "127.0.0.1".to_string()),
+        server_rcon_timeout: env::var("SERVER_RCON_TIMEOUT")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse::<u64>()?,
+        server_rcon_retries: env::var("SERVER_RCON_RETRIES")
+            .unwrap_or_else(|_| "2".to_string())
+            .parse::<u32>()?,
+        server_rcon_quiet_period: env::var("SERVER_RCON_QUIET_PERIOD")
+            .unwrap_or_else(|_| "150".to_string())
+            .parse::<u64>()?,
+        server_rcon_max_response_size: env::var("SERVER_RCON_MAX_RESPONSE_SIZE")
+            .unwrap_or_else(|_| "262144".to_string())
+            .parse::<usize>()?,
+        server_rcon_min_interval: env::var("SERVER_RCON_MIN_INTERVAL")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()?,
+        server_log_path: env::var("SERVER_LOG_PATH").ok(),
+        server_log_poll_interval: env::var("SERVER_LOG_POLL_INTERVAL")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse::<u64>()?,
+        server_log_replay: env::var("SERVER_LOG_REPLAY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()?,
+        server_log_retention_days: env::var("SERVER_LOG_RETENTION_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()?,
+        server_health_interval: env::var("SERVER_HEALTH_INTERVAL")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse::<u64>()?,
+        server_health_retention_days: env::var("SERVER_HEALTH_RETENTION_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()?,
+        server_dvars,
+        server_console,
+        servers,
         db_host: env::var("DB_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
         db_port: env::var("DB_PORT")
             .unwrap_or_else(|_| "5432".to_string())
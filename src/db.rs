@@ -15,9 +15,20 @@
  *
  */
 
-use crate::maps::Map;
-use deadpool_postgres::{Config, Pool, Runtime};
+use crate::fleet::ServerDefinition;
+use crate::game::Game;
+use crate::maps::{CatalogMap, Map, MapFilter, MapSettings};
+use crate::matches::FinishedMatch;
+use crate::models::{
+    ConsoleEntry, Gametype, GametypeDefinition, HealthSample, LeaderboardEntry, LeaderboardMetric,
+    Match, MatchDetail, MatchPlayer, PlayerStats, Rotation, RotationEntry,
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Config, Pool, Runtime, Transaction};
 use tokio_postgres::NoTls;
+use tokio_postgres::Row;
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
 
 pub(crate) type DBClient = deadpool_postgres::Client;
 pub(crate) type DBPool = Pool;
@@ -38,17 +49,995 @@ pub(crate) fn create_pool(
     Ok(pg_cfg.create_pool(Some(Runtime::Tokio1), NoTls)?)
 }
 
-pub(crate) async fn get_active_maps(db_client: &DBClient) -> anyhow::Result<Vec<Map>> {
+// Without a limit every map matching the filter is read
+pub(crate) async fn get_active_maps(
+    db_client: &DBClient,
+    filter: &MapFilter,
+    limit: Option<i64>,
+) -> anyhow::Result<Vec<Map>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "SELECT m.id AS id, m.tag AS tag, m.name AS name, m.original AS original, m.seq AS seq FROM codmap m WHERE m.active = TRUE AND ($1::varchar IS NULL OR NOT EXISTS (SELECT 1 FROM codmap_gametype c WHERE c.codmap_id = m.id) OR EXISTS (SELECT 1 FROM codmap_gametype c WHERE c.codmap_id = m.id AND c.gametype = $1)) AND ($2::varchar IS NULL OR m.tag ILIKE $2 OR m.name ILIKE $2) AND ($3::boolean IS NULL OR m.original = $3) AND ($4::varchar IS NULL OR ({0}) > (SELECT {1} FROM codmap p WHERE p.tag = $4)) ORDER BY {0} LIMIT $5",
+            filter.sort.to_sql("m"),
+            filter.sort.to_sql("p")
+        ))
+        .await?;
+
+    Ok(db_client
+        .query(
+            &stmt,
+            &[
+                &filter.gametype.as_ref().map(Gametype::to_tag),
+                &filter.pattern(),
+                &filter.original,
+                &filter.cursor,
+                &limit,
+            ],
+        )
+        .await?
+        .iter()
+        .map(|row| Map::new(row.get(0), row.get(1), row.get(2), row.get(3), row.get(4)))
+        .collect())
+}
+
+// A map without any gametype listed is not restricted
+pub(crate) async fn is_map_compatible(
+    db_client: &DBClient,
+    tag: &str,
+    gametype: &Gametype,
+) -> anyhow::Result<bool> {
     let stmt = db_client
         .prepare(
-            "SELECT m.tag AS tag, m.name AS name FROM codmap m WHERE m.active = TRUE ORDER BY name",
+            "SELECT NOT EXISTS (SELECT 1 FROM codmap_gametype c JOIN codmap m ON m.id = c.codmap_id WHERE m.tag = $1) OR EXISTS (SELECT 1 FROM codmap_gametype c JOIN codmap m ON m.id = c.codmap_id WHERE m.tag = $1 AND c.gametype = $2)",
         )
         .await?;
+    Ok(db_client
+        .query_one(&stmt, &[&tag, &gametype.to_tag()])
+        .await?
+        .get(0))
+}
 
+// For every entry, in order: whether its map is active, None when it is not
+// in the catalog, and whether the map supports its gametype
+pub(crate) async fn get_rotation_entries_support(
+    db_client: &DBClient,
+    entries: &[RotationEntry],
+) -> anyhow::Result<Vec<(Option<bool>, bool)>> {
+    let maps = entries
+        .iter()
+        .map(|entry| entry.map.as_str())
+        .collect::<Vec<&str>>();
+    let gametypes = entries
+        .iter()
+        .map(|entry| entry.gametype.to_tag())
+        .collect::<Vec<&str>>();
+    let stmt = db_client
+        .prepare(
+            "SELECT m.active AS active, (m.id IS NULL OR NOT EXISTS (SELECT 1 FROM codmap_gametype c WHERE c.codmap_id = m.id) OR EXISTS (SELECT 1 FROM codmap_gametype c WHERE c.codmap_id = m.id AND c.gametype = e.gametype)) AS compatible FROM unnest($1::varchar[], $2::varchar[]) WITH ORDINALITY AS e(map, gametype, ord) LEFT JOIN codmap m ON m.tag = e.map ORDER BY e.ord",
+        )
+        .await?;
     Ok(db_client
+        .query(&stmt, &[&maps, &gametypes])
+        .await?
+        .iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect())
+}
+
+const CATALOG_MAP_QUERY: &str = "SELECT m.id AS id, m.tag AS tag, m.name AS name, m.original AS original, m.active AS active, m.seq AS seq, COALESCE(array_agg(c.gametype ORDER BY c.gametype) FILTER (WHERE c.gametype IS NOT NULL), '{}') AS gametypes FROM codmap m LEFT JOIN codmap_gametype c ON c.codmap_id = m.id";
+
+fn catalog_map_from_row(row: &Row) -> anyhow::Result<CatalogMap> {
+    Ok(CatalogMap {
+        id: row.get(0),
+        settings: MapSettings {
+            tag: row.get(1),
+            name: row.get(2),
+            original: row.get(3),
+            active: row.get(4),
+            seq: row.get(5),
+            gametypes: row
+                .get::<_, Vec<&str>>(6)
+                .into_iter()
+                .map(str::parse::<Gametype>)
+                .collect::<Result<Vec<Gametype>, _>>()?,
+        },
+    })
+}
+
+pub(crate) async fn get_catalog_maps(db_client: &DBClient) -> anyhow::Result<Vec<CatalogMap>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "{} GROUP BY m.id ORDER BY m.seq, m.tag",
+            CATALOG_MAP_QUERY
+        ))
+        .await?;
+    db_client
         .query(&stmt, &[])
         .await?
         .iter()
-        .map(|row| Map::new(row.get(0), row.get(1)))
-        .collect::<Vec<Map>>())
+        .map(catalog_map_from_row)
+        .collect()
+}
+
+pub(crate) async fn get_catalog_map(
+    db_client: &DBClient,
+    id: &Uuid,
+) -> anyhow::Result<Option<CatalogMap>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "{} WHERE m.id = $1 GROUP BY m.id",
+            CATALOG_MAP_QUERY
+        ))
+        .await?;
+    db_client
+        .query_opt(&stmt, &[id])
+        .await?
+        .as_ref()
+        .map(catalog_map_from_row)
+        .transpose()
+}
+
+pub(crate) async fn create_map(
+    db_client: &mut DBClient,
+    settings: &MapSettings,
+) -> anyhow::Result<Uuid> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare("INSERT INTO codmap (tag, name, original, active, seq) VALUES ($1, $2, $3, $4, $5) RETURNING id")
+        .await?;
+    let id: Uuid = transaction
+        .query_one(
+            &stmt,
+            &[
+                &settings.tag,
+                &settings.name,
+                &settings.original,
+                &settings.active,
+                &settings.seq,
+            ],
+        )
+        .await?
+        .get(0);
+
+    insert_map_gametypes(&transaction, &id, &settings.gametypes).await?;
+    transaction.commit().await?;
+
+    Ok(id)
+}
+
+pub(crate) async fn update_map(
+    db_client: &mut DBClient,
+    id: &Uuid,
+    settings: &MapSettings,
+) -> anyhow::Result<bool> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare("UPDATE codmap SET tag = $2, name = $3, original = $4, active = $5, seq = $6 WHERE id = $1")
+        .await?;
+    let updated = transaction
+        .execute(
+            &stmt,
+            &[
+                id,
+                &settings.tag,
+                &settings.name,
+                &settings.original,
+                &settings.active,
+                &settings.seq,
+            ],
+        )
+        .await?;
+    if updated == 0 {
+        return Ok(false);
+    }
+
+    let stmt = transaction
+        .prepare("DELETE FROM codmap_gametype WHERE codmap_id = $1")
+        .await?;
+    transaction.execute(&stmt, &[id]).await?;
+
+    insert_map_gametypes(&transaction, id, &settings.gametypes).await?;
+    transaction.commit().await?;
+
+    Ok(true)
+}
+
+async fn insert_map_gametypes(
+    transaction: &Transaction<'_>,
+    codmap_id: &Uuid,
+    gametypes: &[Gametype],
+) -> anyhow::Result<()> {
+    let stmt = transaction
+        .prepare("INSERT INTO codmap_gametype (codmap_id, gametype) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .await?;
+    for gametype in gametypes {
+        transaction
+            .execute(&stmt, &[codmap_id, &gametype.to_tag()])
+            .await?;
+    }
+    Ok(())
+}
+
+// Maps get the position they have in the list as seq, the others keep
+// theirs. Nothing changes when one of the ids is unknown.
+pub(crate) async fn reorder_maps(db_client: &mut DBClient, ids: &[Uuid]) -> anyhow::Result<bool> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare("UPDATE codmap SET seq = $2 WHERE id = $1")
+        .await?;
+    for (seq, id) in ids.iter().enumerate() {
+        let seq = i32::try_from(seq)?;
+        if transaction.execute(&stmt, &[id, &seq]).await? == 0 {
+            return Ok(false);
+        }
+    }
+    transaction.commit().await?;
+
+    Ok(true)
+}
+
+pub(crate) async fn delete_map(db_client: &DBClient, id: &Uuid) -> anyhow::Result<bool> {
+    let stmt = db_client
+        .prepare("DELETE FROM codmap WHERE id = $1")
+        .await?;
+    Ok(db_client.execute(&stmt, &[id]).await? > 0)
+}
+
+// None when the tag is not in the catalog, whether it is active otherwise
+pub(crate) async fn get_map_active(
+    db_client: &DBClient,
+    tag: &str,
+) -> anyhow::Result<Option<bool>> {
+    let stmt = db_client
+        .prepare("SELECT m.active AS active FROM codmap m WHERE m.tag = $1")
+        .await?;
+    Ok(db_client
+        .query_opt(&stmt, &[&tag])
+        .await?
+        .map(|row| row.get(0)))
+}
+
+pub(crate) async fn get_servers(db_client: &DBClient) -> anyhow::Result<Vec<ServerDefinition>> {
+    let stmt = db_client
+        .prepare(
+            "SELECT s.id AS id, s.name AS name, s.host AS host, s.port AS port, s.game AS game, s.rcon_password AS rcon_password, s.log_path AS log_path FROM game_server s WHERE s.active = TRUE ORDER BY id",
+        )
+        .await?;
+
+    db_client
+        .query(&stmt, &[])
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(ServerDefinition {
+                id: row.get(0),
+                name: row.get(1),
+                host: row.get(2),
+                port: u16::try_from(row.get::<_, i32>(3))?,
+                game: row.get::<_, &str>(4).parse::<Game>()?,
+                rcon_password: row.get(5),
+                log_path: row.get(6),
+            })
+        })
+        .collect()
+}
+
+const GAMETYPE_COLUMNS: &str = "g.tag AS tag, g.name AS name, g.description AS description, g.team_based AS team_based, g.source_mod AS source_mod";
+
+fn gametype_from_row(row: &Row) -> anyhow::Result<GametypeDefinition> {
+    Ok(GametypeDefinition {
+        tag: row.get::<_, &str>(0).parse::<Gametype>()?,
+        name: row.get(1),
+        description: row.get(2),
+        team_based: row.get(3),
+        source_mod: row.get(4),
+    })
+}
+
+pub(crate) async fn get_gametypes(db_client: &DBClient) -> anyhow::Result<Vec<GametypeDefinition>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "SELECT {} FROM gametype g ORDER BY g.source_mod NULLS FIRST, g.name",
+            GAMETYPE_COLUMNS
+        ))
+        .await?;
+    db_client
+        .query(&stmt, &[])
+        .await?
+        .iter()
+        .map(gametype_from_row)
+        .collect()
+}
+
+pub(crate) async fn get_gametype(
+    db_client: &DBClient,
+    tag: &Gametype,
+) -> anyhow::Result<Option<GametypeDefinition>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "SELECT {} FROM gametype g WHERE g.tag = $1",
+            GAMETYPE_COLUMNS
+        ))
+        .await?;
+    db_client
+        .query_opt(&stmt, &[&tag.to_tag()])
+        .await?
+        .as_ref()
+        .map(gametype_from_row)
+        .transpose()
+}
+
+pub(crate) async fn create_gametype(
+    db_client: &DBClient,
+    gametype: &GametypeDefinition,
+) -> anyhow::Result<()> {
+    let stmt = db_client
+        .prepare("INSERT INTO gametype (tag, name, description, team_based, source_mod) VALUES ($1, $2, $3, $4, $5)")
+        .await?;
+    db_client
+        .execute(
+            &stmt,
+            &[
+                &gametype.tag.to_tag(),
+                &gametype.name,
+                &gametype.description,
+                &gametype.team_based,
+                &gametype.source_mod,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn update_gametype(
+    db_client: &DBClient,
+    gametype: &GametypeDefinition,
+) -> anyhow::Result<bool> {
+    let stmt = db_client
+        .prepare("UPDATE gametype SET name = $2, description = $3, team_based = $4, source_mod = $5 WHERE tag = $1")
+        .await?;
+    Ok(db_client
+        .execute(
+            &stmt,
+            &[
+                &gametype.tag.to_tag(),
+                &gametype.name,
+                &gametype.description,
+                &gametype.team_based,
+                &gametype.source_mod,
+            ],
+        )
+        .await?
+        > 0)
+}
+
+pub(crate) async fn delete_gametype(db_client: &DBClient, tag: &Gametype) -> anyhow::Result<bool> {
+    let stmt = db_client
+        .prepare("DELETE FROM gametype WHERE tag = $1")
+        .await?;
+    Ok(db_client.execute(&stmt, &[&tag.to_tag()]).await? > 0)
+}
+
+pub(crate) async fn get_rotations(db_client: &DBClient) -> anyhow::Result<Vec<Rotation>> {
+    let stmt = db_client
+        .prepare("SELECT r.id AS id, r.name AS name FROM rotation r ORDER BY name")
+        .await?;
+
+    let mut rotations = db_client
+        .query(&stmt, &[])
+        .await?
+        .iter()
+        .map(|row| Rotation {
+            id: row.get(0),
+            name: row.get(1),
+            entries: Vec::new(),
+        })
+        .collect::<Vec<Rotation>>();
+
+    let stmt = db_client
+        .prepare(
+            "SELECT e.rotation_id AS rotation_id, e.gametype AS gametype, m.tag AS tag FROM rotation_entry e JOIN codmap m ON m.id = e.codmap_id ORDER BY e.rotation_id, e.seq",
+        )
+        .await?;
+
+    for row in db_client.query(&stmt, &[]).await? {
+        let rotation_id: Uuid = row.get(0);
+        if let Some(rotation) = rotations.iter_mut().find(|r| r.id == rotation_id) {
+            rotation.entries.push(RotationEntry {
+                gametype: row.get::<_, &str>(1).parse::<Gametype>()?,
+                map: row.get(2),
+            });
+        }
+    }
+
+    Ok(rotations)
+}
+
+pub(crate) async fn get_rotation(
+    db_client: &DBClient,
+    id: &Uuid,
+) -> anyhow::Result<Option<Rotation>> {
+    let stmt = db_client
+        .prepare("SELECT r.id AS id, r.name AS name FROM rotation r WHERE r.id = $1")
+        .await?;
+
+    let Some(row) = db_client.query_opt(&stmt, &[id]).await? else {
+        return Ok(None);
+    };
+
+    let stmt = db_client
+        .prepare(
+            "SELECT e.gametype AS gametype, m.tag AS tag FROM rotation_entry e JOIN codmap m ON m.id = e.codmap_id WHERE e.rotation_id = $1 ORDER BY e.seq",
+        )
+        .await?;
+
+    let entries = db_client
+        .query(&stmt, &[id])
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(RotationEntry {
+                gametype: row.get::<_, &str>(0).parse::<Gametype>()?,
+                map: row.get(1),
+            })
+        })
+        .collect::<anyhow::Result<Vec<RotationEntry>>>()?;
+
+    Ok(Some(Rotation {
+        id: row.get(0),
+        name: row.get(1),
+        entries,
+    }))
+}
+
+pub(crate) async fn create_rotation(
+    db_client: &mut DBClient,
+    name: &str,
+    entries: &[RotationEntry],
+) -> anyhow::Result<Uuid> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare("INSERT INTO rotation (name) VALUES ($1) RETURNING id")
+        .await?;
+    let id: Uuid = transaction.query_one(&stmt, &[&name]).await?.get(0);
+
+    insert_rotation_entries(&transaction, &id, entries).await?;
+    transaction.commit().await?;
+
+    Ok(id)
+}
+
+pub(crate) async fn update_rotation(
+    db_client: &mut DBClient,
+    id: &Uuid,
+    name: &str,
+    entries: &[RotationEntry],
+) -> anyhow::Result<bool> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare("UPDATE rotation SET name = $2 WHERE id = $1")
+        .await?;
+    if transaction.execute(&stmt, &[id, &name]).await? == 0 {
+        return Ok(false);
+    }
+
+    let stmt = transaction
+        .prepare("DELETE FROM rotation_entry WHERE rotation_id = $1")
+        .await?;
+    transaction.execute(&stmt, &[id]).await?;
+
+    insert_rotation_entries(&transaction, id, entries).await?;
+    transaction.commit().await?;
+
+    Ok(true)
+}
+
+pub(crate) async fn delete_rotation(db_client: &DBClient, id: &Uuid) -> anyhow::Result<bool> {
+    let stmt = db_client
+        .prepare("DELETE FROM rotation WHERE id = $1")
+        .await?;
+    Ok(db_client.execute(&stmt, &[id]).await? > 0)
+}
+
+// Duplicate keys and rows still referenced elsewhere are the caller's
+// fault, so they are told apart from the other database errors.
+pub(crate) fn is_conflict(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<tokio_postgres::Error>()
+        .and_then(|error| error.code())
+        .is_some_and(|code| {
+            code == &SqlState::UNIQUE_VIOLATION || code == &SqlState::FOREIGN_KEY_VIOLATION
+        })
+}
+
+async fn insert_rotation_entries(
+    transaction: &Transaction<'_>,
+    rotation_id: &Uuid,
+    entries: &[RotationEntry],
+) -> anyhow::Result<()> {
+    let stmt = transaction
+        .prepare(
+            "INSERT INTO rotation_entry (rotation_id, seq, gametype, codmap_id) SELECT $1, $2, $3, m.id FROM codmap m WHERE m.tag = $4",
+        )
+        .await?;
+
+    for (seq, entry) in entries.iter().enumerate() {
+        let seq = i32::try_from(seq)?;
+        let inserted = transaction
+            .execute(
+                &stmt,
+                &[rotation_id, &seq, &entry.gametype.to_tag(), &entry.map],
+            )
+            .await?;
+        if inserted == 0 {
+            return Err(anyhow::anyhow!("Unknown map: {}", entry.map));
+        }
+    }
+
+    Ok(())
+}
+
+// The line is registered first: when it is already known the whole event
+// has been counted before and nothing else is touched.
+pub(crate) async fn record_player_stats(
+    db_client: &mut DBClient,
+    server_id: &str,
+    log_file: &str,
+    log_offset: i64,
+    line: &str,
+    time: &DateTime<Utc>,
+    stats: &[PlayerStats],
+) -> anyhow::Result<bool> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare(
+            "INSERT INTO player_stats_line (server_id, log_file, log_offset, checksum, recorded_at) VALUES ($1, $2, $3, md5($4), now()) ON CONFLICT DO NOTHING",
+        )
+        .await?;
+    if transaction
+        .execute(&stmt, &[&server_id, &log_file, &log_offset, &line])
+        .await?
+        == 0
+    {
+        return Ok(false);
+    }
+
+    let stmt = transaction
+        .prepare(
+            "INSERT INTO player_stats (guid, name, kills, deaths, headshots, suicides, team_kills, damage, time_played, first_seen, last_seen) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10) ON CONFLICT (guid) DO UPDATE SET name = EXCLUDED.name, kills = player_stats.kills + EXCLUDED.kills, deaths = player_stats.deaths + EXCLUDED.deaths, headshots = player_stats.headshots + EXCLUDED.headshots, suicides = player_stats.suicides + EXCLUDED.suicides, team_kills = player_stats.team_kills + EXCLUDED.team_kills, damage = player_stats.damage + EXCLUDED.damage, time_played = player_stats.time_played + EXCLUDED.time_played, last_seen = GREATEST(player_stats.last_seen, EXCLUDED.last_seen)",
+        )
+        .await?;
+
+    for player in stats {
+        transaction
+            .execute(
+                &stmt,
+                &[
+                    &player.guid,
+                    &player.name,
+                    &player.kills,
+                    &player.deaths,
+                    &player.headshots,
+                    &player.suicides,
+                    &player.team_kills,
+                    &player.damage,
+                    &player.time_played,
+                    time,
+                ],
+            )
+            .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(true)
+}
+
+// Lines are only remembered for as long as a log may be read again
+pub(crate) async fn prune_player_stats_lines(
+    db_client: &DBClient,
+    before: &DateTime<Utc>,
+) -> anyhow::Result<u64> {
+    let stmt = db_client
+        .prepare("DELETE FROM player_stats_line WHERE recorded_at < $1")
+        .await?;
+    Ok(db_client.execute(&stmt, &[before]).await?)
+}
+
+pub(crate) async fn get_player_stats(
+    db_client: &DBClient,
+    guid: &str,
+) -> anyhow::Result<Option<PlayerStats>> {
+    let stmt = db_client
+        .prepare(
+            "SELECT p.guid AS guid, p.name AS name, p.kills AS kills, p.deaths AS deaths, p.headshots AS headshots, p.suicides AS suicides, p.team_kills AS team_kills, p.damage AS damage, p.time_played AS time_played FROM player_stats p WHERE p.guid = $1",
+        )
+        .await?;
+
+    Ok(db_client
+        .query_opt(&stmt, &[&guid.to_lowercase()])
+        .await?
+        .map(|row| PlayerStats {
+            guid: row.get(0),
+            name: row.get(1),
+            kills: row.get(2),
+            deaths: row.get(3),
+            headshots: row.get(4),
+            suicides: row.get(5),
+            team_kills: row.get(6),
+            damage: row.get(7),
+            time_played: row.get(8),
+        }))
+}
+
+// The map is kept as played, and linked to the catalog only when it is
+// there. None when the match was already recorded.
+pub(crate) async fn record_match(
+    db_client: &mut DBClient,
+    finished: &FinishedMatch,
+) -> anyhow::Result<Option<Uuid>> {
+    let transaction = db_client.transaction().await?;
+
+    let stmt = transaction
+        .prepare(
+            "INSERT INTO game_match (server_id, map, codmap_id, gametype, started_at, ended_at, duration, allies_kills, axis_kills, winner, log_file, log_offset, checksum) VALUES ($1, $2, (SELECT m.id FROM codmap m WHERE m.tag = $2), $3, $4, $5, $6, $7, $8, $9, $10, $11, md5($12)) ON CONFLICT DO NOTHING RETURNING id",
+        )
+        .await?;
+    let Some(row) = transaction
+        .query_opt(
+            &stmt,
+            &[
+                &finished.server_id,
+                &finished.map,
+                &finished.gametype.to_tag(),
+                &finished.started_at,
+                &finished.ended_at,
+                &finished.duration,
+                &finished.allies_kills,
+                &finished.axis_kills,
+                &finished.winner,
+                &finished.log_file,
+                &finished.log_offset,
+                &finished.line,
+            ],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let id: Uuid = row.get(0);
+
+    let stmt = transaction
+        .prepare(
+            "INSERT INTO match_player (match_id, guid, name, team, score, won, kills, deaths, headshots, suicides, team_kills, damage, time_played) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+        )
+        .await?;
+
+    for player in &finished.players {
+        transaction
+            .execute(
+                &stmt,
+                &[
+                    &id,
+                    &player.stats.guid,
+                    &player.stats.name,
+                    &player.team,
+                    &player.score,
+                    &player.won,
+                    &player.stats.kills,
+                    &player.stats.deaths,
+                    &player.stats.headshots,
+                    &player.stats.suicides,
+                    &player.stats.team_kills,
+                    &player.stats.damage,
+                    &player.stats.time_played,
+                ],
+            )
+            .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(Some(id))
+}
+
+const MATCH_COLUMNS: &str = "g.id AS id, g.server_id AS server_id, g.map AS map, g.gametype AS gametype, g.started_at AS started_at, g.ended_at AS ended_at, g.duration AS duration, g.allies_kills AS allies_kills, g.axis_kills AS axis_kills, g.winner AS winner";
+
+fn match_from_row(row: &Row) -> anyhow::Result<Match> {
+    Ok(Match {
+        id: row.get(0),
+        server_id: row.get(1),
+        map: row.get(2),
+        gametype: row.get::<_, &str>(3).parse::<Gametype>()?,
+        started_at: row.get(4),
+        ended_at: row.get(5),
+        duration: row.get(6),
+        allies_kills: row.get(7),
+        axis_kills: row.get(8),
+        winner: row.get(9),
+    })
+}
+
+// Newest first, together with the number of matches in every page
+pub(crate) async fn get_matches(
+    db_client: &DBClient,
+    server_id: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<(Vec<Match>, i64)> {
+    let stmt = db_client
+        .prepare(
+            "SELECT count(*) FROM game_match g WHERE ($1::varchar IS NULL OR g.server_id = $1)",
+        )
+        .await?;
+    let total: i64 = db_client.query_one(&stmt, &[&server_id]).await?.get(0);
+
+    let stmt = db_client
+        .prepare(&format!(
+            "SELECT {} FROM game_match g WHERE ($1::varchar IS NULL OR g.server_id = $1) ORDER BY g.started_at DESC, g.id LIMIT $2 OFFSET $3",
+            MATCH_COLUMNS
+        ))
+        .await?;
+
+    let matches = db_client
+        .query(&stmt, &[&server_id, &limit, &offset])
+        .await?
+        .iter()
+        .map(match_from_row)
+        .collect::<anyhow::Result<Vec<Match>>>()?;
+
+    Ok((matches, total))
+}
+
+pub(crate) async fn get_match(
+    db_client: &DBClient,
+    id: &Uuid,
+) -> anyhow::Result<Option<MatchDetail>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "SELECT {} FROM game_match g WHERE g.id = $1",
+            MATCH_COLUMNS
+        ))
+        .await?;
+
+    let Some(row) = db_client.query_opt(&stmt, &[id]).await? else {
+        return Ok(None);
+    };
+
+    let stmt = db_client
+        .prepare(
+            "SELECT p.guid AS guid, p.name AS name, p.team AS team, p.score AS score, p.won AS won, p.kills AS kills, p.deaths AS deaths, p.headshots AS headshots, p.suicides AS suicides, p.team_kills AS team_kills, p.damage AS damage, p.time_played AS time_played FROM match_player p WHERE p.match_id = $1 ORDER BY p.score DESC, p.deaths, p.name",
+        )
+        .await?;
+
+    let players = db_client
+        .query(&stmt, &[id])
+        .await?
+        .iter()
+        .map(|row| MatchPlayer {
+            stats: PlayerStats {
+                guid: row.get(0),
+                name: row.get(1),
+                kills: row.get(5),
+                deaths: row.get(6),
+                headshots: row.get(7),
+                suicides: row.get(8),
+                team_kills: row.get(9),
+                damage: row.get(10),
+                time_played: row.get(11),
+            },
+            team: row.get(2),
+            score: row.get(3),
+            won: row.get(4),
+        })
+        .collect::<Vec<MatchPlayer>>();
+
+    Ok(Some(MatchDetail {
+        summary: match_from_row(&row)?,
+        players,
+    }))
+}
+
+// Rankings are computed from the final lines of the matches rather than from
+// single kills, so the rows grouped grow with games played, not with events.
+pub(crate) async fn get_leaderboard(
+    db_client: &DBClient,
+    metric: LeaderboardMetric,
+    gametype: Option<&Gametype>,
+    map: Option<&str>,
+    since: Option<DateTime<Utc>>,
+    min_games: i64,
+    limit: i64,
+) -> anyhow::Result<Vec<LeaderboardEntry>> {
+    let stmt = db_client
+        .prepare(&format!(
+            "SELECT p.guid AS guid, (array_agg(p.name ORDER BY g.started_at DESC))[1] AS name, count(*) AS games, count(*) FILTER (WHERE p.won) AS wins, sum(p.kills)::bigint AS kills, sum(p.deaths)::bigint AS deaths, sum(p.headshots)::bigint AS headshots, sum(p.score)::bigint AS score, sum(p.time_played)::bigint AS time_played, {} AS value FROM match_player p JOIN game_match g ON g.id = p.match_id WHERE ($1::varchar IS NULL OR g.gametype = $1) AND ($2::varchar IS NULL OR g.map = $2) AND ($3::timestamptz IS NULL OR g.started_at >= $3) GROUP BY p.guid HAVING count(*) >= $4 ORDER BY value DESC, games DESC, guid LIMIT $5",
+            metric.to_sql()
+        ))
+        .await?;
+
+    Ok(db_client
+        .query(
+            &stmt,
+            &[
+                &gametype.map(|gametype| gametype.to_tag()),
+                &map,
+                &since,
+                &min_games,
+                &limit,
+            ],
+        )
+        .await?
+        .iter()
+        .zip(1..)
+        .map(|(row, rank)| LeaderboardEntry {
+            rank,
+            guid: row.get(0),
+            name: row.get(1),
+            games: row.get(2),
+            wins: row.get(3),
+            kills: row.get(4),
+            deaths: row.get(5),
+            headshots: row.get(6),
+            score: row.get(7),
+            time_played: row.get(8),
+            value: row.get(9),
+        })
+        .collect())
+}
+
+pub(crate) async fn prune_health(
+    db_client: &DBClient,
+    before: &DateTime<Utc>,
+) -> anyhow::Result<u64> {
+    let stmt = db_client
+        .prepare("DELETE FROM server_health WHERE checked_at < $1")
+        .await?;
+    Ok(db_client.execute(&stmt, &[before]).await?)
+}
+
+pub(crate) async fn record_health(
+    db_client: &DBClient,
+    server_id: &str,
+    sample: &HealthSample,
+) -> anyhow::Result<()> {
+    let stmt = db_client
+        .prepare(
+            "INSERT INTO server_health (server_id, checked_at, online, latency, players, max_players, map, gametype) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .await?;
+    db_client
+        .execute(
+            &stmt,
+            &[
+                &server_id,
+                &sample.time,
+                &sample.online,
+                &sample.latency,
+                &sample.players,
+                &sample.max_players,
+                &sample.map,
+                &sample.gametype,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+// Number of checks since the given time, and how many of them were answered
+pub(crate) async fn get_uptime(
+    db_client: &DBClient,
+    server_id: &str,
+    since: Option<DateTime<Utc>>,
+) -> anyhow::Result<(i64, i64)> {
+    let stmt = db_client
+        .prepare(
+            "SELECT count(*) AS checks, count(*) FILTER (WHERE h.online) AS online FROM server_health h WHERE h.server_id = $1 AND ($2::timestamptz IS NULL OR h.checked_at >= $2)",
+        )
+        .await?;
+    let row = db_client.query_one(&stmt, &[&server_id, &since]).await?;
+    Ok((row.get(0), row.get(1)))
+}
+
+// Entries are created before the command reaches the server, so that nothing
+// runs without leaving a trace even if the reply never comes.
+pub(crate) async fn create_console_entry(
+    db_client: &DBClient,
+    server_id: &str,
+    command: &str,
+    allowed: bool,
+    error: Option<&str>,
+    remote_addr: Option<&str>,
+) -> anyhow::Result<Uuid> {
+    let stmt = db_client
+        .prepare(
+            "INSERT INTO console_log (server_id, command, allowed, error, remote_addr, requested_at) VALUES ($1, $2, $3, $4, $5, now()) RETURNING id",
+        )
+        .await?;
+    Ok(db_client
+        .query_one(
+            &stmt,
+            &[&server_id, &command, &allowed, &error, &remote_addr],
+        )
+        .await?
+        .get(0))
+}
+
+pub(crate) async fn finish_console_entry(
+    db_client: &DBClient,
+    id: &Uuid,
+    reply: Option<&str>,
+    error: Option<&str>,
+) -> anyhow::Result<()> {
+    let stmt = db_client
+        .prepare("UPDATE console_log SET reply = $2, error = $3 WHERE id = $1")
+        .await?;
+    db_client.execute(&stmt, &[id, &reply, &error]).await?;
+    Ok(())
+}
+
+// Newest first, together with the number of entries in every page
+pub(crate) async fn get_console_log(
+    db_client: &DBClient,
+    server_id: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<(Vec<ConsoleEntry>, i64)> {
+    let stmt = db_client
+        .prepare(
+            "SELECT count(*) FROM console_log c WHERE ($1::varchar IS NULL OR c.server_id = $1)",
+        )
+        .await?;
+    let total: i64 = db_client.query_one(&stmt, &[&server_id]).await?.get(0);
+
+    let stmt = db_client
+        .prepare(
+            "SELECT c.id AS id, c.server_id AS server_id, c.command AS command, c.allowed AS allowed, c.reply AS reply, c.error AS error, c.remote_addr AS remote_addr, c.requested_at AS requested_at FROM console_log c WHERE ($1::varchar IS NULL OR c.server_id = $1) ORDER BY c.requested_at DESC, c.id LIMIT $2 OFFSET $3",
+        )
+        .await?;
+
+    let entries = db_client
+        .query(&stmt, &[&server_id, &limit, &offset])
+        .await?
+        .iter()
+        .map(|row| ConsoleEntry {
+            id: row.get(0),
+            server_id: row.get(1),
+            command: row.get(2),
+            allowed: row.get(3),
+            reply: row.get(4),
+            error: row.get(5),
+            remote_addr: row.get(6),
+            requested_at: row.get(7),
+        })
+        .collect();
+
+    Ok((entries, total))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::env;
+
+    // Tests reading or writing rows are ignored by default. They run with
+    // `cargo test -- --ignored` against the database of the DB_* variables,
+    // doc/sql/init.sql loaded.
+    pub(crate) fn test_pool() -> DBPool {
+        create_pool(
+            &env::var("DB_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
+            env::var("DB_PORT")
+                .unwrap_or_else(|_| "5432".to_string())
+                .parse::<u16>()
+                .unwrap(),
+            Some(env::var("DB_USER").unwrap_or_else(|_| "olg".to_string())),
+            Some(env::var("DB_PASSWORD").unwrap_or_else(|_| "olg".to_string())),
+            &env::var("DB_NAME").unwrap_or_else(|_| "olg".to_string()),
+        )
+        .unwrap()
+    }
 }
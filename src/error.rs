@@ -15,12 +15,21 @@
  *
  */
 
+use crate::rcon::RconError;
 use actix_web::{HttpResponse, ResponseError, http::StatusCode};
 use serde::Serialize;
 use std::fmt;
 
 #[derive(Debug)]
-pub struct ApiError(anyhow::Error);
+pub enum ApiError {
+    Internal(anyhow::Error),
+    Rcon(RconError),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Unprocessable(String),
+}
 
 #[derive(Serialize)]
 struct ErrorResponse {
@@ -29,29 +38,60 @@ struct ErrorResponse {
 
 impl fmt::Display for ApiError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.0)
+        match self {
+            ApiError::Internal(error) => write!(f, "{:?}", error),
+            ApiError::Rcon(error) => write!(f, "{}", error),
+            ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::Conflict(message)
+            | ApiError::Unprocessable(message) => write!(f, "{}", message),
+        }
     }
 }
 
 impl ResponseError for ApiError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::INTERNAL_SERVER_ERROR
+        match self {
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Rcon(RconError::Timeout) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::Rcon(
+                RconError::InvalidArgument(_)
+                | RconError::MapNotFound(_)
+                | RconError::UnknownCommand(_),
+            ) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Rcon(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
     }
 
     fn error_response(&self) -> HttpResponse {
-        let message = format!("Internal server error: {}", self.0);
+        let message = match self {
+            ApiError::Internal(error) => format!("Internal server error: {}", error),
+            _ => self.to_string(),
+        };
         HttpResponse::build(self.status_code()).json(ErrorResponse { message })
     }
 }
 
 impl From<anyhow::Error> for ApiError {
     fn from(error: anyhow::Error) -> Self {
-        ApiError(error)
+        ApiError::Internal(error)
     }
 }
 
 impl From<deadpool_postgres::PoolError> for ApiError {
     fn from(error: deadpool_postgres::PoolError) -> Self {
-        ApiError(anyhow::anyhow!(error))
+        ApiError::Internal(anyhow::anyhow!(error))
+    }
+}
+
+impl From<RconError> for ApiError {
+    fn from(error: RconError) -> Self {
+        ApiError::Rcon(error)
     }
 }
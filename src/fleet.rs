@@ -0,0 +1,167 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::game::Game;
+use crate::gamelog::GameEvents;
+use crate::monitor::HealthBoard;
+use crate::rcon::RconSettings;
+use crate::server::CodServer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Length of game_server.id
+const MAX_SERVER_ID_LENGTH: usize = 32;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerDefinition {
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub game: Game,
+    #[serde(skip_serializing)]
+    pub rcon_password: String,
+    // games_mp.log of the server, when it is reachable from this host
+    #[serde(default, skip_serializing)]
+    pub log_path: Option<String>,
+}
+
+// Every registered game server, each one with its own RCON worker, the feed
+// their events are published on and the outcome of their last health check.
+// The id is the path segment used by the API, so it is kept URL-friendly.
+#[derive(Clone, Debug, Default)]
+pub struct Fleet {
+    servers: BTreeMap<String, (ServerDefinition, CodServer)>,
+    events: GameEvents,
+    health: HealthBoard,
+}
+
+impl Fleet {
+    pub fn new(
+        definitions: Vec<ServerDefinition>,
+        settings: &RconSettings,
+    ) -> anyhow::Result<Self> {
+        let mut fleet = Fleet::default();
+        for definition in definitions {
+            let server = CodServer::with_settings(
+                &definition.host,
+                definition.port,
+                &definition.rcon_password,
+                settings.clone(),
+            )
+            .with_game(definition.game);
+            fleet.insert(definition, server)?;
+        }
+        Ok(fleet)
+    }
+
+    pub fn insert(
+        &mut self,
+        definition: ServerDefinition,
+        server: CodServer,
+    ) -> anyhow::Result<()> {
+        let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+        if definition.id.is_empty()
+            || definition.id.len() > MAX_SERVER_ID_LENGTH
+            || !definition.id.chars().all(valid)
+        {
+            anyhow::bail!("Invalid server id: {}", definition.id);
+        }
+        if self.servers.contains_key(&definition.id) {
+            anyhow::bail!("Duplicate server id: {}", definition.id);
+        }
+        let server = server.with_id(&definition.id);
+        self.servers
+            .insert(definition.id.clone(), (definition, server));
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CodServer> {
+        self.servers.get(id).map(|(_, server)| server)
+    }
+
+    pub fn events(&self) -> &GameEvents {
+        &self.events
+    }
+
+    pub fn health(&self) -> &HealthBoard {
+        &self.health
+    }
+
+    pub fn definitions(&self) -> Vec<&ServerDefinition> {
+        self.servers
+            .values()
+            .map(|(definition, _)| definition)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn definition(id: &str, port: u16) -> ServerDefinition {
+        ServerDefinition {
+            id: id.to_string(),
+            name: format!("Server {}", id),
+            host: "127.0.0.1".to_string(),
+            port,
+            game: Game::Cod4,
+            rcon_password: "test_password".to_string(),
+            log_path: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fleet() -> anyhow::Result<()> {
+        let fleet = Fleet::new(
+            vec![definition("tdm", 28960), definition("sd", 28961)],
+            &RconSettings::default(),
+        )?;
+        assert_eq!(
+            fleet.get("sd").map(ToString::to_string),
+            Some("127.0.0.1:28961".to_string())
+        );
+        assert!(fleet.get("scrim").is_none());
+        let ids = fleet
+            .definitions()
+            .iter()
+            .map(|definition| definition.id.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(ids, vec!["sd", "tdm"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fleet_invalid_ids() {
+        let settings = RconSettings::default();
+        assert!(Fleet::new(vec![definition("tdm", 1), definition("tdm", 2)], &settings).is_err());
+        assert!(Fleet::new(vec![definition("", 1)], &settings).is_err());
+        assert!(Fleet::new(vec![definition("Public TDM", 1)], &settings).is_err());
+        assert!(Fleet::new(vec![definition(&"a".repeat(33), 1)], &settings).is_err());
+        assert!(Fleet::new(vec![definition(&"a".repeat(32), 1)], &settings).is_ok());
+    }
+
+    #[test]
+    fn test_definition_hides_password() {
+        let json = serde_json::to_value(definition("tdm", 28960)).unwrap();
+        assert_eq!(json["id"], "tdm");
+        assert_eq!(json["game"], "cod4");
+        assert!(json.get("rcon_password").is_none());
+    }
+}
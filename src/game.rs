@@ -0,0 +1,180 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::models::ServerStatus;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct InvalidGame(String);
+
+impl Display for InvalidGame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid game: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidGame {}
+
+// What sets a title apart from the others speaking the same Quake 3 derived
+// OOB/RCON protocol. The protocol itself is handled by CodServer, which
+// asks the title whenever a reply has to be understood.
+pub trait GameServer: Debug + Send + Sync {
+    // Stock tags accepted by g_gametype
+    fn gametypes(&self) -> &'static [&'static str];
+
+    // Reply to map, map_restart, fast_restart and map_rotate when the engine
+    // has started loading a map
+    fn is_map_loading(&self, reply: &str) -> bool;
+
+    fn is_missing_map(&self, reply: &str) -> bool;
+
+    // Body of the reply to status, without the "print" header
+    fn parse_status(&self, body: &str) -> Option<ServerStatus> {
+        body.parse().ok()
+    }
+}
+
+#[derive(Debug)]
+pub struct Cod4;
+
+impl GameServer for Cod4 {
+    fn gametypes(&self) -> &'static [&'static str] {
+        &["dm", "war", "dom", "sd", "koth", "sab"]
+    }
+
+    fn is_map_loading(&self, reply: &str) -> bool {
+        reply.starts_with("print\n==== ShutdownGame")
+    }
+
+    fn is_missing_map(&self, reply: &str) -> bool {
+        reply.contains("Can't find map")
+    }
+}
+
+// Call of Duty 2 prints the same status table and the same errors as the
+// later engine. Only its stock gametypes are its own; the lines it prints
+// while a map starts to load changed across patches, so every reply that is
+// not an error is taken as the map loading.
+#[derive(Debug)]
+pub struct Cod2;
+
+impl GameServer for Cod2 {
+    fn gametypes(&self) -> &'static [&'static str] {
+        &["dm", "tdm", "sd", "ctf", "hq"]
+    }
+
+    fn is_map_loading(&self, reply: &str) -> bool {
+        reply.starts_with("print\n")
+            && !reply.contains("Unknown command")
+            && !self.is_missing_map(reply)
+    }
+
+    fn is_missing_map(&self, reply: &str) -> bool {
+        reply.contains("Can't find map")
+    }
+}
+
+// Title run by a game server, as written in the configuration
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Game {
+    Cod2,
+    #[default]
+    Cod4,
+}
+
+impl Display for Game {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Game::Cod2 => write!(f, "cod2"),
+            Game::Cod4 => write!(f, "cod4"),
+        }
+    }
+}
+
+impl FromStr for Game {
+    type Err = InvalidGame;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cod2" => Ok(Game::Cod2),
+            "cod4" => Ok(Game::Cod4),
+            _ => Err(InvalidGame(s.to_string())),
+        }
+    }
+}
+
+impl Game {
+    pub fn server(&self) -> &'static dyn GameServer {
+        match self {
+            Game::Cod2 => &Cod2,
+            Game::Cod4 => &Cod4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_from_configuration() {
+        assert_eq!("cod4".parse::<Game>().unwrap(), Game::Cod4);
+        assert_eq!("cod2".parse::<Game>().unwrap(), Game::Cod2);
+        assert!("cod5".parse::<Game>().is_err());
+        assert_eq!(
+            serde_json::from_str::<Game>("\"cod4\"").unwrap(),
+            Game::Cod4
+        );
+        assert_eq!(Game::Cod4.to_string(), "cod4");
+    }
+
+    #[test]
+    fn test_cod4_replies() {
+        let cod4 = Game::Cod4.server();
+        assert!(cod4.gametypes().contains(&"war"));
+        assert!(!cod4.gametypes().contains(&"tdm"));
+        assert!(cod4.is_map_loading("print\n==== ShutdownGame ===="));
+        assert!(!cod4.is_map_loading("print\nUnknown command \"map_rotat\""));
+        assert!(cod4.is_missing_map("print\nCan't find map maps/mp/mp_foo.d3dbsp\n"));
+    }
+
+    #[test]
+    fn test_cod2_replies() {
+        let cod2 = Game::Cod2.server();
+        assert!(cod2.gametypes().contains(&"ctf"));
+        assert!(!cod2.gametypes().contains(&"war"));
+        assert!(cod2.is_map_loading("print\n"));
+        assert!(!cod2.is_map_loading("print\nUnknown command \"map_rotat\"\n"));
+        assert!(!cod2.is_map_loading("print\nCan't find map maps/mp/mp_foo.d3dbsp\n"));
+        assert!(cod2.is_missing_map("print\nCan't find map maps/mp/mp_foo.d3dbsp\n"));
+
+        let status = cod2
+            .parse_status(
+                "map: mp_carentan
+num score ping guid   name            lastmsg address               qport rate
+--- ----- ---- ------ --------------- ------- --------------------- ----- -----
+  0     5   60 354981 ^1Sardy^7               0 93.44.12.7:28960      31543 25000
+",
+            )
+            .unwrap();
+        assert_eq!(status.map, "mp_carentan");
+        assert_eq!(status.players[0].guid, "354981");
+    }
+}
@@ -0,0 +1,420 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::models::{AdminAction, GameEvent, LogEntry};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+const CHANNEL_SIZE: usize = 1024;
+
+// The uptime, the log file and the offset of the line in it are only known
+// for events read from the log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerEvent {
+    pub server_id: String,
+    pub time: DateTime<Utc>,
+    pub uptime: Option<u32>,
+    #[serde(default, skip_serializing)]
+    pub log_file: Option<String>,
+    pub offset: Option<u64>,
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
+
+// In-process fan-out of the events of every game server. Subscribers that
+// fall behind lose the oldest events instead of slowing down the tailers.
+#[derive(Clone, Debug)]
+pub struct GameEvents {
+    sender: broadcast::Sender<ServerEvent>,
+}
+
+impl Default for GameEvents {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_SIZE);
+        Self { sender }
+    }
+}
+
+impl GameEvents {
+    pub fn publish(&self, event: ServerEvent) {
+        // Nobody listening is not an error, the event is simply dropped
+        let _ = self.sender.send(event);
+    }
+
+    pub fn publish_action(&self, server_id: &str, action: AdminAction) {
+        self.publish(ServerEvent {
+            server_id: server_id.to_string(),
+            time: Utc::now(),
+            uptime: None,
+            log_file: None,
+            offset: None,
+            event: GameEvent::AdminAction(action),
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.sender.subscribe()
+    }
+}
+
+pub fn spawn_tailer(
+    events: GameEvents,
+    server_id: &str,
+    path: &str,
+    poll_interval: Duration,
+    replay: bool,
+) -> tokio::task::JoinHandle<()> {
+    let tailer = Tailer {
+        server_id: server_id.to_string(),
+        path: PathBuf::from(path),
+        events,
+        file: None,
+        inode: 0,
+        generation: 0,
+        file_id: String::new(),
+        position: 0,
+        pending: Vec::new(),
+        from_start: replay,
+    };
+    tokio::spawn(tailer.run(poll_interval))
+}
+
+// Follows games_mp.log like `tail -F`. Unless asked to replay it, the first
+// time the file is opened reading starts from its end, so history is not
+// read again on every restart.
+// A file that shrinks has been truncated and is read again from the start;
+// a different inode behind the same path means it has been rotated, so what
+// is left of the old one is read before switching to the new one.
+struct Tailer {
+    server_id: String,
+    path: PathBuf,
+    events: GameEvents,
+    file: Option<File>,
+    inode: u64,
+    // Truncations of the current file seen so far
+    generation: u32,
+    file_id: String,
+    position: u64,
+    pending: Vec<u8>,
+    from_start: bool,
+}
+
+impl Tailer {
+    async fn run(mut self, poll_interval: Duration) {
+        info!("Tailing {} for {}", self.path.display(), self.server_id);
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(error) = self.poll().await {
+                warn!("Error reading {}: {}", self.path.display(), error);
+                self.file = None;
+            }
+        }
+    }
+
+    async fn poll(&mut self) -> std::io::Result<()> {
+        let metadata = match tokio::fs::metadata(&self.path).await {
+            Ok(metadata) => metadata,
+            // Between the rename and the creation of the new file
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error),
+        };
+
+        if self.file.is_some() && metadata.ino() != self.inode {
+            debug!("{} has been rotated", self.path.display());
+            self.read_available().await?;
+            self.file = None;
+        }
+
+        if self.file.is_none() {
+            self.file = Some(File::open(&self.path).await?);
+            self.inode = metadata.ino();
+            self.generation = 0;
+            self.file_id = file_identity(&metadata, self.generation);
+            self.position = if self.from_start { 0 } else { metadata.len() };
+            self.pending.clear();
+            self.from_start = true;
+        }
+
+        if metadata.len() < self.position {
+            debug!("{} has been truncated", self.path.display());
+            self.generation += 1;
+            self.file_id = file_identity(&metadata, self.generation);
+            self.position = 0;
+            self.pending.clear();
+        }
+
+        self.read_available().await
+    }
+
+    async fn read_available(&mut self) -> std::io::Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        file.seek(SeekFrom::Start(self.position)).await?;
+        let mut buf = Vec::new();
+        let read = file.read_to_end(&mut buf).await?;
+        self.position += read as u64;
+        self.pending.extend_from_slice(&buf);
+
+        // The last line may still be half written: it stays pending until
+        // its newline shows up.
+        let mut entries = Vec::new();
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let offset = self.position - self.pending.len() as u64;
+            let line = self.pending.drain(..=end).collect::<Vec<u8>>();
+            match String::from_utf8_lossy(&line).parse::<LogEntry>() {
+                Ok(entry) => entries.push((offset, entry)),
+                Err(error) => debug!("Skipping log line of {}: {}", self.server_id, error),
+            }
+        }
+
+        let uptimes = entries
+            .iter()
+            .map(|(_, entry)| entry.uptime)
+            .collect::<Vec<u32>>();
+        for ((offset, entry), time) in entries.into_iter().zip(wall_clock(&uptimes, Utc::now())) {
+            self.events.publish(ServerEvent {
+                server_id: self.server_id.clone(),
+                time,
+                uptime: Some(entry.uptime),
+                log_file: Some(self.file_id.clone()),
+                offset: Some(offset),
+                event: entry.event,
+            });
+        }
+        Ok(())
+    }
+}
+
+// The log only has the uptime of the server process. The last line read is
+// taken as written now and the ones before it are placed back by their
+// uptime, so that lines read in one go, a log replayed from its start or a
+// match caught up after a slow poll, keep the time they were written at.
+// Where the uptime goes back the server has been restarted, and the lines
+// before are only known to come before the restart.
+fn wall_clock(uptimes: &[u32], now: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let mut times = vec![now; uptimes.len()];
+    for index in (0..uptimes.len().saturating_sub(1)).rev() {
+        let elapsed = uptimes[index + 1].saturating_sub(uptimes[index]);
+        times[index] = times[index + 1] - chrono::Duration::seconds(i64::from(elapsed));
+    }
+    times
+}
+
+// Tells apart files that followed each other behind the same path, so that
+// a line of a new log is not mistaken for the one at the same offset of the
+// old log. Inodes are reused once freed, the creation time is not. A file
+// truncated in place, like logrotate's copytruncate does, keeps both, so the
+// truncations seen by the tailer tell its contents apart.
+fn file_identity(metadata: &std::fs::Metadata, generation: u32) -> String {
+    let created = metadata
+        .created()
+        .ok()
+        .and_then(|created| created.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|created| created.as_nanos())
+        .unwrap_or_default();
+    format!(
+        "{:x}:{:x}:{:x}:{:x}",
+        metadata.dev(),
+        metadata.ino(),
+        created,
+        generation
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GAMES_MP_LOG_FIXTURE;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    fn log_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("olg-{}-{}.log", name, std::process::id()))
+    }
+
+    fn append(path: &PathBuf, text: &str) -> std::io::Result<()> {
+        use std::io::Write;
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)?
+            .write_all(text.as_bytes())
+    }
+
+    async fn drain(receiver: &mut broadcast::Receiver<ServerEvent>) -> Vec<ServerEvent> {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut events = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(event) => events.push(event),
+                Err(TryRecvError::Empty) => return events,
+                Err(error) => panic!("{}", error),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tail_appended_lines() -> anyhow::Result<()> {
+        let path = log_path("append");
+        std::fs::write(&path, "  0:00 ShutdownGame:\n")?;
+
+        let events = GameEvents::default();
+        let mut receiver = events.subscribe();
+        let tailer = spawn_tailer(
+            events,
+            "tdm",
+            path.to_str().unwrap(),
+            Duration::from_millis(20),
+            false,
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (first, second) = GAMES_MP_LOG_FIXTURE.split_at(GAMES_MP_LOG_FIXTURE.len() / 2);
+        append(&path, first)?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        append(&path, second)?;
+
+        let received = drain(&mut receiver).await;
+        tailer.abort();
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(received.len(), 13);
+        assert!(received.iter().all(|event| event.server_id == "tdm"));
+        assert!(matches!(received[0].event, GameEvent::InitGame(_)));
+        assert_eq!(
+            received[0].offset,
+            GAMES_MP_LOG_FIXTURE
+                .find("  0:00 InitGame")
+                .map(|offset| offset as u64 + 21)
+        );
+        assert_eq!(received[12].event, GameEvent::ShutdownGame);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tail_replay() -> anyhow::Result<()> {
+        let path = log_path("replay");
+        std::fs::write(&path, GAMES_MP_LOG_FIXTURE)?;
+
+        let events = GameEvents::default();
+        let mut receiver = events.subscribe();
+        let tailer = spawn_tailer(
+            events,
+            "scrim",
+            path.to_str().unwrap(),
+            Duration::from_millis(20),
+            true,
+        );
+        let received = drain(&mut receiver).await;
+        tailer.abort();
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(received.len(), 13);
+        // InitGame at 0:00, ShutdownGame at 10:05
+        assert_eq!((received[12].time - received[0].time).num_seconds(), 605);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wall_clock() {
+        let now = DateTime::UNIX_EPOCH + chrono::Duration::days(1);
+        let seconds = |times: Vec<DateTime<Utc>>| {
+            times
+                .iter()
+                .map(|time| (*time - now).num_seconds())
+                .collect::<Vec<i64>>()
+        };
+        assert!(wall_clock(&[], now).is_empty());
+        assert_eq!(seconds(wall_clock(&[0, 31, 605], now)), vec![-605, -574, 0]);
+        // Restarted after 10:05
+        assert_eq!(
+            seconds(wall_clock(&[600, 605, 0, 12], now)),
+            vec![-17, -12, -12, 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tail_truncation_and_rotation() -> anyhow::Result<()> {
+        let path = log_path("rotate");
+        let rotated = log_path("rotate-old");
+        std::fs::write(&path, GAMES_MP_LOG_FIXTURE)?;
+
+        let events = GameEvents::default();
+        let mut receiver = events.subscribe();
+        let tailer = spawn_tailer(
+            events,
+            "sd",
+            path.to_str().unwrap(),
+            Duration::from_millis(20),
+            false,
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(drain(&mut receiver).await.is_empty());
+
+        std::fs::write(&path, " 10:05 ShutdownGame:\n")?;
+        let truncated = drain(&mut receiver).await;
+        assert_eq!(truncated.len(), 1);
+        assert_eq!(truncated[0].uptime, Some(605));
+
+        // The same line at the same offset, after another truncation
+        std::fs::write(&path, "")?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        std::fs::write(&path, " 10:05 ShutdownGame:\n")?;
+        let again = drain(&mut receiver).await;
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].offset, truncated[0].offset);
+        assert_ne!(again[0].log_file, truncated[0].log_file);
+
+        std::fs::rename(&path, &rotated)?;
+        std::fs::write(&path, "  0:00 ExitLevel: executed\n")?;
+        let received = drain(&mut receiver).await;
+        tailer.abort();
+        std::fs::remove_file(&path)?;
+        std::fs::remove_file(&rotated)?;
+
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].event, GameEvent::ExitLevel);
+        assert_ne!(received[0].log_file, truncated[0].log_file);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_log_event() {
+        let event = ServerEvent {
+            server_id: "tdm".to_string(),
+            time: DateTime::UNIX_EPOCH,
+            uptime: Some(12),
+            log_file: Some("fd01:2a:0:0".to_string()),
+            offset: Some(1024),
+            event: GameEvent::ExitLevel,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["server_id"], "tdm");
+        assert_eq!(json["type"], "exit_level");
+        assert_eq!(json["uptime"], 12);
+        assert!(json.get("log_file").is_none());
+    }
+}
@@ -15,101 +15,2021 @@
  *
  */
 
+use crate::auth::{self, AdminToken};
+use crate::command::RconCommand;
 use crate::db::{self, DBPool};
 use crate::error::ApiError;
-use crate::models::Gametype;
+use crate::fleet::Fleet;
+use crate::gamelog::ServerEvent;
+use crate::maps::{MapFilter, MapSettings, MapSort};
+use crate::metrics;
+use crate::models::{
+    AdminAction, ConsolePolicy, CursorPage, DvarAllowlist, GameEvent, Gametype, GametypeDefinition,
+    Leaderboard, LeaderboardMetric, MAX_CONSOLE_COMMAND_LENGTH, MAX_MAP_ROTATION_LENGTH, Page,
+    Player, PlayerTarget, PublicPlayer, RotationEntry, ServerHealth, TimeWindow, command_name,
+    map_rotation, strip_colors,
+};
+use crate::rcon::RconError;
 use crate::server::CodServer;
-use actix_web::middleware::Logger;
+use actix_web::middleware::{Logger, from_fn};
 use actix_web::web::Data;
-use actix_web::{App, HttpResponse, HttpServer, Responder, web};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, Responder, web};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
 
-async fn maps(db_pool: Data<DBPool>) -> Result<HttpResponse, ApiError> {
+// Proxies tend to drop connections that stay silent for too long
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+const DEFAULT_MIN_GAMES: i64 = 5;
+
+#[derive(Deserialize, Serialize, Default)]
+struct MapsQuery {
+    gametype: Option<Gametype>,
+    q: Option<String>,
+    original: Option<bool>,
+    sort: Option<MapSort>,
+    cursor: Option<String>,
+    per_page: Option<i64>,
+}
+
+impl MapsQuery {
+    // No page size when the request has no paging parameter at all
+    fn into_filter(self) -> Result<(MapFilter, Option<i64>), ApiError> {
+        let paginated = self.cursor.is_some() || self.per_page.is_some();
+        let per_page = self.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&per_page) {
+            return Err(ApiError::Unprocessable(format!(
+                "Page size must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+        let filter = MapFilter {
+            gametype: self.gametype,
+            search: self.q.filter(|q| !q.trim().is_empty()),
+            original: self.original,
+            sort: self.sort.unwrap_or_default(),
+            cursor: self.cursor,
+        };
+        Ok((filter, paginated.then_some(per_page)))
+    }
+}
+
+// Clients asking for no page get the bare array of every map, as the first
+// version of the endpoint did. Otherwise one more map than asked is read to
+// know whether another page follows.
+async fn maps(
+    db_pool: Data<DBPool>,
+    query: web::Query<MapsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (filter, per_page) = query.into_inner().into_filter()?;
+    let db_client = db_pool.get().await?;
+    if let Some(cursor) = &filter.cursor
+        && db::get_map_active(&db_client, cursor).await?.is_none()
+    {
+        return Err(ApiError::Unprocessable(format!(
+            "Unknown cursor {}",
+            cursor
+        )));
+    }
+    let Some(per_page) = per_page else {
+        let maps = db::get_active_maps(&db_client, &filter, None).await?;
+        return Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(maps));
+    };
+
+    let mut items = db::get_active_maps(&db_client, &filter, Some(per_page + 1)).await?;
+    let next_cursor = if items.len() > usize::try_from(per_page).map_err(anyhow::Error::from)? {
+        items.pop();
+        items.last().map(|map| map.tag().to_string())
+    } else {
+        None
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(CursorPage {
+            items,
+            per_page,
+            next_cursor,
+        }))
+}
+
+async fn gametypes(db_pool: Data<DBPool>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let gametypes = db::get_gametypes(&db_client).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(gametypes))
+}
+
+async fn gametype(
+    db_pool: Data<DBPool>,
+    tag: web::Path<Gametype>,
+) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let gametype = db::get_gametype(&db_client, &tag)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Gametype {} not found", tag.to_tag())))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(gametype))
+}
+
+async fn create_gametype(
+    db_pool: Data<DBPool>,
+    request_body: web::Json<GametypeDefinition>,
+) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    match db::create_gametype(&db_client, &request_body).await {
+        Ok(()) => Ok(HttpResponse::Created()
+            .content_type("application/json")
+            .json(request_body.into_inner())),
+        Err(error) if db::is_conflict(&error) => Err(ApiError::Conflict(format!(
+            "Gametype {} already exists",
+            request_body.tag.to_tag()
+        ))),
+        Err(error) => Err(error.into()),
+    }
+}
+
+// The body is the same as for creation. Rotations refer to gametypes by
+// tag, so the one in the body has to be the one in the path.
+async fn update_gametype(
+    db_pool: Data<DBPool>,
+    tag: web::Path<Gametype>,
+    request_body: web::Json<GametypeDefinition>,
+) -> Result<HttpResponse, ApiError> {
+    let gametype = request_body.into_inner();
+    if gametype.tag != *tag {
+        return Err(ApiError::Unprocessable(format!(
+            "Gametype {} cannot be renamed to {}",
+            tag.to_tag(),
+            gametype.tag.to_tag()
+        )));
+    }
+    let db_client = db_pool.get().await?;
+    if !db::update_gametype(&db_client, &gametype).await? {
+        return Err(ApiError::NotFound(format!(
+            "Gametype {} not found",
+            gametype.tag.to_tag()
+        )));
+    }
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(gametype))
+}
+
+// Gametypes still used by a rotation are kept, the rotation has to go first
+async fn delete_gametype(
+    db_pool: Data<DBPool>,
+    tag: web::Path<Gametype>,
+) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    match db::delete_gametype(&db_client, &tag).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Err(ApiError::NotFound(format!(
+            "Gametype {} not found",
+            tag.to_tag()
+        ))),
+        Err(error) if db::is_conflict(&error) => Err(ApiError::Conflict(format!(
+            "Gametype {} is used by a rotation",
+            tag.to_tag()
+        ))),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct GametypeMapRequest {
+    gametype: Gametype,
+    map: String,
+}
+
+async fn map_restart(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    report(
+        &fleet,
+        &server_id,
+        "map_restart",
+        None,
+        cod_server.map_restart().await,
+    )?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn fast_restart(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    report(
+        &fleet,
+        &server_id,
+        "fast_restart",
+        None,
+        cod_server.fast_restart().await,
+    )?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn players(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let status = cod_server.status().await?;
+    Ok(HttpResponse::Ok().content_type("application/json").json(
+        status
+            .players
+            .iter()
+            .map(PublicPlayer::from)
+            .collect::<Vec<PublicPlayer>>(),
+    ))
+}
+
+async fn admin_players(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let status = cod_server.status().await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(status.players))
+}
+
+async fn info(fleet: Data<Fleet>, server_id: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let info = cod_server.get_info().await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(info))
+}
+
+async fn status(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let status = cod_server.get_status().await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(status))
+}
+
+async fn map_rotate(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    report(
+        &fleet,
+        &server_id,
+        "map_rotate",
+        None,
+        cod_server.map_rotate().await,
+    )?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Gametype and map are looked up in the catalogs before the server is
+// touched: an unknown one is a 404, a map that has been retired or that
+// does not support the gametype a 422. A map that could never be sent is
+// refused before that.
+async fn gametype_map(
+    db_pool: Data<DBPool>,
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<GametypeMapRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    RconCommand::new("map").word(&request_body.map)?;
+    let db_client = db_pool.get().await?;
+    let gametype = db::get_gametype(&db_client, &request_body.gametype)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Gametype {} not found",
+                request_body.gametype.to_tag()
+            ))
+        })?;
+    if !cod_server.supports_gametype(&gametype) {
+        return Err(unsupported_gametype(&gametype));
+    }
+    match db::get_map_active(&db_client, &request_body.map).await? {
+        None => {
+            return Err(ApiError::NotFound(format!(
+                "Map {} not found",
+                request_body.map
+            )));
+        }
+        Some(false) => {
+            return Err(ApiError::Unprocessable(format!(
+                "Map {} is not an active map",
+                request_body.map
+            )));
+        }
+        Some(true) => {}
+    }
+    if !db::is_map_compatible(&db_client, &request_body.map, &request_body.gametype).await? {
+        return Err(ApiError::Unprocessable(format!(
+            "Map {} does not support gametype {}",
+            request_body.map,
+            request_body.gametype.to_tag()
+        )));
+    }
+    change_gametype_map(&fleet, &server_id, &request_body).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn unsupported_gametype(gametype: &GametypeDefinition) -> ApiError {
+    ApiError::Unprocessable(format!(
+        "{} is not a gametype of this game",
+        gametype.tag.to_tag()
+    ))
+}
+
+async fn change_gametype_map(
+    fleet: &Fleet,
+    server_id: &str,
+    request: &GametypeMapRequest,
+) -> Result<(), ApiError> {
+    let cod_server = cod_server(fleet, server_id)?;
+    let result = cod_server
+        .gametype_map(request.gametype.to_tag(), &request.map)
+        .await;
+    report(
+        fleet,
+        server_id,
+        "gametype_map",
+        Some(format!("{} {}", request.gametype.to_tag(), request.map)),
+        result,
+    )?;
+    Ok(())
+}
+
+#[derive(Deserialize, Serialize)]
+struct DvarRequest {
+    value: String,
+}
+
+async fn get_dvar(
+    fleet: Data<Fleet>,
+    dvar_allowlist: Data<DvarAllowlist>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    let cod_server = cod_server(&fleet, &server_id)?;
+    if dvar_allowlist.get(&name).is_none() {
+        return Err(ApiError::Forbidden(format!("Dvar {} is not allowed", name)));
+    }
+    let dvar = cod_server
+        .get_dvar(&name)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Dvar {} not found", name)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(dvar))
+}
+
+async fn set_dvar(
+    fleet: Data<Fleet>,
+    dvar_allowlist: Data<DvarAllowlist>,
+    path: web::Path<(String, String)>,
+    request_body: web::Json<DvarRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let Some(policy) = dvar_allowlist.get(&name).filter(|policy| policy.writable) else {
+        return Err(ApiError::Forbidden(format!(
+            "Dvar {} is not writable",
+            name
+        )));
+    };
+    policy
+        .validate(&request_body.value)
+        .map_err(|error| ApiError::Unprocessable(error.to_string()))?;
+    let result = cod_server.set_dvar(&name, &request_body.value).await;
+    let dvar = report(&fleet, &server_id, "set_dvar", Some(name), result)?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(dvar))
+}
+
+#[derive(Deserialize, Serialize)]
+struct ConsoleRequest {
+    command: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ConsoleResponse {
+    id: Uuid,
+    reply: String,
+}
+
+// Denied commands are recorded too, and nothing is sent to the server unless
+// its entry could be recorded first. Only the audit log keeps the arguments,
+// the event feed gets the name of the command.
+async fn console(
+    db_pool: Data<DBPool>,
+    fleet: Data<Fleet>,
+    console_policy: Data<ConsolePolicy>,
+    server_id: web::Path<String>,
+    req: HttpRequest,
+    request_body: web::Json<ConsoleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let command = request_body.command.trim();
+    // Too long to be audited, so it is not sent either
+    if command.chars().count() > MAX_CONSOLE_COMMAND_LENGTH {
+        return Err(ApiError::Unprocessable(format!(
+            "Command is longer than {} characters",
+            MAX_CONSOLE_COMMAND_LENGTH
+        )));
+    }
+    let remote_addr = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(str::to_string);
+    let db_client = db_pool.get().await?;
+
+    if let Err(error) = console_policy.check(command) {
+        let error = error.to_string();
+        db::create_console_entry(
+            &db_client,
+            &server_id,
+            command,
+            false,
+            Some(&error),
+            remote_addr.as_deref(),
+        )
+        .await?;
+        fleet.events().publish_action(
+            &server_id,
+            AdminAction {
+                action: "console".to_string(),
+                target: Some(command_name(command)),
+                error: Some(error.clone()),
+            },
+        );
+        return Err(ApiError::Forbidden(error));
+    }
+
+    let id = db::create_console_entry(
+        &db_client,
+        &server_id,
+        command,
+        true,
+        None,
+        remote_addr.as_deref(),
+    )
+    .await?;
+    let result = cod_server.console(command).await;
+    let (reply, error) = match &result {
+        Ok(reply) => (Some(reply.as_str()), None),
+        Err(error) => (None, Some(error.to_string())),
+    };
+    db::finish_console_entry(&db_client, &id, reply, error.as_deref()).await?;
+    let reply = report(
+        &fleet,
+        &server_id,
+        "console",
+        Some(command_name(command)),
+        result,
+    )?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(ConsoleResponse { id, reply }))
+}
+
+async fn console_log(
+    db_pool: Data<DBPool>,
+    query: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (page, per_page) = query.bounds()?;
     let db_client = db_pool.get().await?;
-    let maps = db::get_active_maps(&db_client).await?;
+    let (items, total) = db::get_console_log(
+        &db_client,
+        query.server.as_deref(),
+        per_page,
+        (page - 1) * per_page,
+    )
+    .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(Page {
+            items,
+            page,
+            per_page,
+            total,
+        }))
+}
+
+#[derive(Deserialize, Serialize)]
+struct UnbanRequest {
+    name: String,
+}
+
+async fn kick(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<PlayerTarget>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let result = cod_server.kick(&request_body).await;
+    let player = report(
+        &fleet,
+        &server_id,
+        "kick",
+        Some(request_body.to_string()),
+        result,
+    )?;
+    affected_player(player, &request_body)
+}
+
+async fn temp_ban(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<PlayerTarget>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let result = cod_server.temp_ban(&request_body).await;
+    let player = report(
+        &fleet,
+        &server_id,
+        "temp_ban",
+        Some(request_body.to_string()),
+        result,
+    )?;
+    affected_player(player, &request_body)
+}
+
+async fn ban(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<PlayerTarget>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let result = cod_server.ban(&request_body).await;
+    let player = report(
+        &fleet,
+        &server_id,
+        "ban",
+        Some(request_body.to_string()),
+        result,
+    )?;
+    affected_player(player, &request_body)
+}
+
+async fn unban(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<UnbanRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let result = cod_server.unban(&request_body.name).await;
+    report(
+        &fleet,
+        &server_id,
+        "unban",
+        Some(request_body.name.clone()),
+        result,
+    )?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize, Serialize)]
+struct MessageRequest {
+    message: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TellRequest {
+    target: PlayerTarget,
+    message: String,
+}
+
+// Nothing would be sent for a message without visible text
+fn check_message(message: &str) -> Result<(), ApiError> {
+    if strip_colors(message).trim().is_empty() {
+        return Err(ApiError::Unprocessable("Message is empty".to_string()));
+    }
+    Ok(())
+}
+
+async fn say(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<MessageRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    check_message(&request_body.message)?;
+    let result = cod_server.say(&request_body.message).await;
+    report(&fleet, &server_id, "say", None, result)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn tell(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<TellRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    check_message(&request_body.message)?;
+    let result = cod_server
+        .tell(&request_body.target, &request_body.message)
+        .await;
+    let player = report(
+        &fleet,
+        &server_id,
+        "tell",
+        Some(request_body.target.to_string()),
+        result,
+    )?;
+    affected_player(player, &request_body.target)
+}
+
+async fn announce(
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    request_body: web::Json<MessageRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let cod_server = cod_server(&fleet, &server_id)?;
+    check_message(&request_body.message)?;
+    let result = cod_server.announce(&request_body.message).await;
+    report(&fleet, &server_id, "announce", None, result)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Every action taken on a game server ends up on the live feed, failed or not
+fn report<T>(
+    fleet: &Fleet,
+    server_id: &str,
+    action: &str,
+    target: Option<String>,
+    result: Result<T, RconError>,
+) -> Result<T, RconError> {
+    fleet.events().publish_action(
+        server_id,
+        AdminAction {
+            action: action.to_string(),
+            target,
+            error: result.as_ref().err().map(ToString::to_string),
+        },
+    );
+    result
+}
+
+fn affected_player(
+    player: Option<Player>,
+    target: &PlayerTarget,
+) -> Result<HttpResponse, ApiError> {
+    match player {
+        Some(player) => Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(player)),
+        None => Err(ApiError::NotFound(format!(
+            "No single player matches {}",
+            target
+        ))),
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct RotationRequest {
+    name: String,
+    entries: Vec<RotationEntry>,
+}
+
+async fn rotations(db_pool: Data<DBPool>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let rotations = db::get_rotations(&db_client).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(rotations))
+}
+
+async fn rotation(db_pool: Data<DBPool>, id: web::Path<Uuid>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let rotation = db::get_rotation(&db_client, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Rotation {} not found", id)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(rotation))
+}
+
+async fn create_rotation(
+    db_pool: Data<DBPool>,
+    request_body: web::Json<RotationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut db_client = db_pool.get().await?;
+    check_rotation_entries(&db_client, &request_body.entries).await?;
+    let id = db::create_rotation(&mut db_client, &request_body.name, &request_body.entries)
+        .await
+        .map_err(|error| rotation_conflict(error, &request_body.name))?;
+    let rotation = db::get_rotation(&db_client, &id).await?;
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .json(rotation))
+}
+
+async fn update_rotation(
+    db_pool: Data<DBPool>,
+    id: web::Path<Uuid>,
+    request_body: web::Json<RotationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut db_client = db_pool.get().await?;
+    check_rotation_entries(&db_client, &request_body.entries).await?;
+    if !db::update_rotation(
+        &mut db_client,
+        &id,
+        &request_body.name,
+        &request_body.entries,
+    )
+    .await
+    .map_err(|error| rotation_conflict(error, &request_body.name))?
+    {
+        return Err(ApiError::NotFound(format!("Rotation {} not found", id)));
+    }
+    let rotation = db::get_rotation(&db_client, &id).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(rotation))
+}
+
+async fn delete_rotation(
+    db_pool: Data<DBPool>,
+    id: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    if !db::delete_rotation(&db_client, &id).await? {
+        return Err(ApiError::NotFound(format!("Rotation {} not found", id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn apply_rotation(
+    db_pool: Data<DBPool>,
+    fleet: Data<Fleet>,
+    path: web::Path<(String, Uuid)>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, id) = path.into_inner();
+    let cod_server = cod_server(&fleet, &server_id)?;
+    let db_client = db_pool.get().await?;
+    let rotation = db::get_rotation(&db_client, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Rotation {} not found", id)))?;
+    if rotation.entries.is_empty() {
+        return Err(ApiError::Unprocessable(format!(
+            "Rotation {} has no entries",
+            rotation.name
+        )));
+    }
+    let gametypes = db::get_gametypes(&db_client).await?;
+    if let Some(gametype) = gametypes.iter().find(|gametype| {
+        !cod_server.supports_gametype(gametype)
+            && rotation
+                .entries
+                .iter()
+                .any(|entry| entry.gametype == gametype.tag)
+    }) {
+        return Err(unsupported_gametype(gametype));
+    }
+    let result = cod_server
+        .set_map_rotation(&rotation.to_map_rotation())
+        .await;
+    report(
+        &fleet,
+        &server_id,
+        "apply_rotation",
+        Some(rotation.name),
+        result,
+    )?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn rotation_conflict(error: anyhow::Error, name: &str) -> ApiError {
+    if db::is_conflict(&error) {
+        ApiError::Conflict(format!("Rotation {} already exists", name))
+    } else {
+        error.into()
+    }
+}
+
+async fn check_rotation_entries(
+    db_client: &db::DBClient,
+    entries: &[RotationEntry],
+) -> Result<(), ApiError> {
+    if map_rotation(entries).len() > MAX_MAP_ROTATION_LENGTH {
+        return Err(ApiError::Unprocessable(format!(
+            "Rotation is longer than {} characters",
+            MAX_MAP_ROTATION_LENGTH
+        )));
+    }
+
+    let gametypes = db::get_gametypes(db_client).await?;
+    if let Some(entry) = entries.iter().find(|entry| {
+        !gametypes
+            .iter()
+            .any(|gametype| gametype.tag == entry.gametype)
+    }) {
+        return Err(ApiError::Unprocessable(format!(
+            "Gametype {} is not in the catalog",
+            entry.gametype.to_tag()
+        )));
+    }
+
+    let support = db::get_rotation_entries_support(db_client, entries).await?;
+    for (entry, (active, compatible)) in entries.iter().zip(support) {
+        if active != Some(true) {
+            return Err(ApiError::Unprocessable(format!(
+                "Map {} is not an active map",
+                entry.map
+            )));
+        }
+        if !compatible {
+            return Err(ApiError::Unprocessable(format!(
+                "Map {} does not support gametype {}",
+                entry.map,
+                entry.gametype.to_tag()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+const MAX_MAP_TAG_LENGTH: usize = 128;
+const MAX_MAP_NAME_LENGTH: usize = 256;
+
+async fn catalog_maps(db_pool: Data<DBPool>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let maps = db::get_catalog_maps(&db_client).await?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .json(maps))
 }
 
-#[derive(Deserialize, Serialize)]
-struct GametypeMapRequest {
-    gametype: Gametype,
-    map: String,
-}
+async fn catalog_map(db_pool: Data<DBPool>, id: web::Path<Uuid>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let map = db::get_catalog_map(&db_client, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Map {} not found", id)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(map))
+}
+
+async fn create_map(
+    db_pool: Data<DBPool>,
+    request_body: web::Json<MapSettings>,
+) -> Result<HttpResponse, ApiError> {
+    let mut db_client = db_pool.get().await?;
+    check_map_settings(&db_client, &request_body).await?;
+    let id = db::create_map(&mut db_client, &request_body)
+        .await
+        .map_err(|error| map_conflict(error, &request_body.tag))?;
+    let map = db::get_catalog_map(&db_client, &id).await?;
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .json(map))
+}
+
+async fn update_map(
+    db_pool: Data<DBPool>,
+    id: web::Path<Uuid>,
+    request_body: web::Json<MapSettings>,
+) -> Result<HttpResponse, ApiError> {
+    let mut db_client = db_pool.get().await?;
+    check_map_settings(&db_client, &request_body).await?;
+    if !db::update_map(&mut db_client, &id, &request_body)
+        .await
+        .map_err(|error| map_conflict(error, &request_body.tag))?
+    {
+        return Err(ApiError::NotFound(format!("Map {} not found", id)));
+    }
+    let map = db::get_catalog_map(&db_client, &id).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(map))
+}
+
+#[derive(Deserialize, Serialize)]
+struct MapOrderRequest {
+    ids: Vec<Uuid>,
+}
+
+async fn reorder_maps(
+    db_pool: Data<DBPool>,
+    request_body: web::Json<MapOrderRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut db_client = db_pool.get().await?;
+    if !db::reorder_maps(&mut db_client, &request_body.ids).await? {
+        return Err(ApiError::NotFound(
+            "Some of the maps to reorder were not found".to_string(),
+        ));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Maps used by a rotation are kept, retire them instead
+async fn delete_map(db_pool: Data<DBPool>, id: web::Path<Uuid>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    match db::delete_map(&db_client, &id).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Err(ApiError::NotFound(format!("Map {} not found", id))),
+        Err(error) if db::is_conflict(&error) => Err(ApiError::Conflict(format!(
+            "Map {} is used by a rotation",
+            id
+        ))),
+        Err(error) => Err(error.into()),
+    }
+}
+
+// The tag ends up in map commands, so it has to be something RCON can send
+async fn check_map_settings(
+    db_client: &db::DBClient,
+    settings: &MapSettings,
+) -> Result<(), ApiError> {
+    RconCommand::new("map").word(&settings.tag)?;
+    if settings.tag.len() > MAX_MAP_TAG_LENGTH {
+        return Err(ApiError::Unprocessable(format!(
+            "Map tag is longer than {} characters",
+            MAX_MAP_TAG_LENGTH
+        )));
+    }
+    if settings
+        .name
+        .as_ref()
+        .is_some_and(|name| name.chars().count() > MAX_MAP_NAME_LENGTH)
+    {
+        return Err(ApiError::Unprocessable(format!(
+            "Map name is longer than {} characters",
+            MAX_MAP_NAME_LENGTH
+        )));
+    }
+
+    let gametypes = db::get_gametypes(db_client).await?;
+    match settings
+        .gametypes
+        .iter()
+        .find(|tag| !gametypes.iter().any(|gametype| &gametype.tag == *tag))
+    {
+        Some(tag) => Err(ApiError::Unprocessable(format!(
+            "Gametype {} is not in the catalog",
+            tag.to_tag()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn map_conflict(error: anyhow::Error, tag: &str) -> ApiError {
+    if db::is_conflict(&error) {
+        ApiError::Conflict(format!("Map {} already exists", tag))
+    } else {
+        error.into()
+    }
+}
+
+async fn player_stats(
+    db_pool: Data<DBPool>,
+    guid: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let stats = db::get_player_stats(&db_client, &guid)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Player {} not found", guid)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(stats))
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct PageQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+    server: Option<String>,
+}
+
+impl PageQuery {
+    // Pages start from 1
+    fn bounds(&self) -> Result<(i64, i64), ApiError> {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+        if page < 1 {
+            return Err(ApiError::Unprocessable(format!("Invalid page: {}", page)));
+        }
+        if !(1..=MAX_PAGE_SIZE).contains(&per_page) {
+            return Err(ApiError::Unprocessable(format!(
+                "Page size must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+        Ok((page, per_page))
+    }
+}
+
+async fn matches(
+    db_pool: Data<DBPool>,
+    query: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (page, per_page) = query.bounds()?;
+    let db_client = db_pool.get().await?;
+    let (items, total) = db::get_matches(
+        &db_client,
+        query.server.as_deref(),
+        per_page,
+        (page - 1) * per_page,
+    )
+    .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(Page {
+            items,
+            page,
+            per_page,
+            total,
+        }))
+}
+
+async fn game_match(db_pool: Data<DBPool>, id: web::Path<Uuid>) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let detail = db::get_match(&db_client, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Match {} not found", id)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(detail))
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct LeaderboardQuery {
+    gametype: Option<Gametype>,
+    map: Option<String>,
+    #[serde(default)]
+    window: TimeWindow,
+    min_games: Option<i64>,
+    limit: Option<i64>,
+}
+
+async fn leaderboard(
+    db_pool: Data<DBPool>,
+    metric: web::Path<LeaderboardMetric>,
+    query: web::Query<LeaderboardQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+    let min_games = query.min_games.unwrap_or(DEFAULT_MIN_GAMES).max(1);
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::Unprocessable(format!(
+            "Limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+    let db_client = db_pool.get().await?;
+    let entries = db::get_leaderboard(
+        &db_client,
+        *metric,
+        query.gametype.as_ref(),
+        query.map.as_deref(),
+        query.window.start(chrono::Utc::now()),
+        min_games,
+        limit,
+    )
+    .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(Leaderboard {
+            metric: *metric,
+            window: query.window,
+            gametype: query.gametype,
+            map: query.map,
+            min_games,
+            entries,
+        }))
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct HealthQuery {
+    #[serde(default)]
+    window: TimeWindow,
+}
+
+async fn server_health(
+    db_client: &db::DBClient,
+    fleet: &Fleet,
+    server_id: &str,
+    window: TimeWindow,
+) -> Result<ServerHealth, ApiError> {
+    let (checks, online) =
+        db::get_uptime(db_client, server_id, window.start(chrono::Utc::now())).await?;
+    Ok(ServerHealth::new(
+        server_id,
+        window,
+        fleet.health().get(server_id),
+        checks,
+        online,
+    ))
+}
+
+async fn fleet_health(
+    db_pool: Data<DBPool>,
+    fleet: Data<Fleet>,
+    query: web::Query<HealthQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let db_client = db_pool.get().await?;
+    let mut health = Vec::new();
+    for definition in fleet.definitions() {
+        health.push(server_health(&db_client, &fleet, &definition.id, query.window).await?);
+    }
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(health))
+}
+
+async fn health_of_server(
+    db_pool: Data<DBPool>,
+    fleet: Data<Fleet>,
+    server_id: web::Path<String>,
+    query: web::Query<HealthQuery>,
+) -> Result<HttpResponse, ApiError> {
+    cod_server(&fleet, &server_id)?;
+    let db_client = db_pool.get().await?;
+    let health = server_health(&db_client, &fleet, &server_id, query.window).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(health))
+}
+
+async fn servers(fleet: Data<Fleet>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(fleet.definitions()))
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct FeedFilter {
+    servers: Option<String>,
+    types: Option<String>,
+    // Set by the admin feed, the public one never carries admin actions
+    #[serde(skip)]
+    admin: bool,
+}
+
+impl FeedFilter {
+    // Both lists are comma separated, a missing one lets everything through
+    fn matches(&self, event: &ServerEvent) -> bool {
+        let allows = |list: &Option<String>, value: &str| {
+            list.as_ref()
+                .is_none_or(|list| list.split(',').any(|item| item.trim() == value))
+        };
+        (self.admin || !matches!(event.event, GameEvent::AdminAction(_)))
+            && allows(&self.servers, &event.server_id)
+            && allows(&self.types, event.event.kind())
+    }
+
+    fn check(&self, fleet: &Fleet) -> Result<(), ApiError> {
+        for id in self.servers.iter().flat_map(|list| list.split(',')) {
+            cod_server(fleet, id.trim())?;
+        }
+        Ok(())
+    }
+}
+
+enum FeedItem {
+    Event(Box<ServerEvent>),
+    Lagged(u64),
+}
+
+impl FeedItem {
+    fn kind(&self) -> &str {
+        match self {
+            FeedItem::Event(event) => event.event.kind(),
+            FeedItem::Lagged(_) => "lagged",
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            FeedItem::Event(event) => serde_json::to_string(event).unwrap_or_default(),
+            FeedItem::Lagged(skipped) => {
+                serde_json::json!({"type": "lagged", "skipped": skipped}).to_string()
+            }
+        }
+    }
+}
+
+// Each client reads from its own bounded slot of the broadcast channel: one
+// that cannot keep up is told how many events it missed instead of holding
+// back the tailers and the other clients.
+async fn next_item(
+    receiver: &mut broadcast::Receiver<ServerEvent>,
+    filter: &FeedFilter,
+) -> Option<FeedItem> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) if filter.matches(&event) => return Some(FeedItem::Event(Box::new(event))),
+            Ok(_) => continue,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                return Some(FeedItem::Lagged(skipped));
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+async fn events_sse(
+    fleet: Data<Fleet>,
+    filter: web::Query<FeedFilter>,
+) -> Result<HttpResponse, ApiError> {
+    sse_feed(&fleet, filter.into_inner())
+}
+
+async fn admin_events_sse(
+    fleet: Data<Fleet>,
+    filter: web::Query<FeedFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let filter = FeedFilter {
+        admin: true,
+        ..filter.into_inner()
+    };
+    sse_feed(&fleet, filter)
+}
+
+fn sse_feed(fleet: &Fleet, filter: FeedFilter) -> Result<HttpResponse, ApiError> {
+    filter.check(fleet)?;
+    let receiver = fleet.events().subscribe();
+    let stream =
+        futures_util::stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+            let frame = match tokio::time::timeout(SSE_KEEPALIVE, next_item(&mut receiver, &filter))
+                .await
+            {
+                Ok(Some(item)) => format!("event: {}\ndata: {}\n\n", item.kind(), item.to_json()),
+                Ok(None) => return None,
+                Err(_) => ":\n\n".to_string(),
+            };
+            Some((
+                Ok::<_, std::convert::Infallible>(web::Bytes::from(frame)),
+                (receiver, filter),
+            ))
+        });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream))
+}
+
+async fn events_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    fleet: Data<Fleet>,
+    filter: web::Query<FeedFilter>,
+) -> Result<HttpResponse, actix_web::Error> {
+    ws_feed(&req, body, &fleet, filter.into_inner())
+}
+
+async fn admin_events_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    fleet: Data<Fleet>,
+    filter: web::Query<FeedFilter>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let filter = FeedFilter {
+        admin: true,
+        ..filter.into_inner()
+    };
+    ws_feed(&req, body, &fleet, filter)
+}
+
+fn ws_feed(
+    req: &HttpRequest,
+    body: web::Payload,
+    fleet: &Fleet,
+    filter: FeedFilter,
+) -> Result<HttpResponse, actix_web::Error> {
+    filter.check(fleet)?;
+    let (response, mut session, mut messages) = actix_ws::handle(req, body)?;
+    let mut receiver = fleet.events().subscribe();
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                item = next_item(&mut receiver, &filter) => {
+                    let Some(item) = item else {
+                        break;
+                    };
+                    if session.text(item.to_json()).await.is_err() {
+                        return;
+                    }
+                }
+                message = messages.recv() => match message {
+                    Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+        let _ = session.close(None).await;
+    });
+    Ok(response)
+}
+
+fn cod_server<'a>(fleet: &'a Fleet, id: &str) -> Result<&'a CodServer, ApiError> {
+    fleet
+        .get(id)
+        .ok_or_else(|| ApiError::NotFound(format!("Server {} not found", id)))
+}
+
+async fn metrics(db_pool: Data<DBPool>, fleet: Data<Fleet>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::render(&db_pool, &fleet)?))
+}
+
+async fn health() -> impl Responder {
+    HttpResponse::NoContent().finish()
+}
+
+// Every route of the admin API, mounted behind the admin token
+fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/events", web::get().to(admin_events_sse))
+        .route("/events/ws", web::get().to(admin_events_ws))
+        .service(
+            web::resource("/servers/{server_id}/dvars/{name}")
+                .route(web::get().to(get_dvar))
+                .route(web::put().to(set_dvar)),
+        )
+        .route("/servers/{server_id}/players", web::get().to(admin_players))
+        .route(
+            "/servers/{server_id}/map_rotate",
+            web::post().to(map_rotate),
+        )
+        .route("/servers/{server_id}/kick", web::post().to(kick))
+        .route("/servers/{server_id}/temp_ban", web::post().to(temp_ban))
+        .route("/servers/{server_id}/ban", web::post().to(ban))
+        .route("/servers/{server_id}/unban", web::post().to(unban))
+        .route("/servers/{server_id}/say", web::post().to(say))
+        .route("/servers/{server_id}/console", web::post().to(console))
+        .route("/console", web::get().to(console_log))
+        .route("/servers/{server_id}/tell", web::post().to(tell))
+        .route("/servers/{server_id}/announce", web::post().to(announce))
+        .route("/gametypes", web::post().to(create_gametype))
+        .service(
+            web::resource("/gametypes/{tag}")
+                .route(web::put().to(update_gametype))
+                .route(web::delete().to(delete_gametype)),
+        )
+        .service(
+            web::resource("/rotations")
+                .route(web::get().to(rotations))
+                .route(web::post().to(create_rotation)),
+        )
+        .service(
+            web::resource("/rotations/{id}")
+                .route(web::get().to(rotation))
+                .route(web::put().to(update_rotation))
+                .route(web::delete().to(delete_rotation)),
+        )
+        .route(
+            "/servers/{server_id}/rotations/{id}/apply",
+            web::post().to(apply_rotation),
+        )
+        .service(
+            web::resource("/maps")
+                .route(web::get().to(catalog_maps))
+                .route(web::post().to(create_map)),
+        )
+        .route("/maps/order", web::put().to(reorder_maps))
+        .service(
+            web::resource("/maps/{id}")
+                .route(web::get().to(catalog_map))
+                .route(web::put().to(update_map))
+                .route(web::delete().to(delete_map)),
+        );
+}
+
+pub async fn run(
+    http_host: &str,
+    http_port: u16,
+    db_pool: DBPool,
+    fleet: Fleet,
+    dvar_allowlist: DvarAllowlist,
+    console_policy: ConsolePolicy,
+    admin_token: AdminToken,
+) -> std::io::Result<()> {
+    HttpServer::new(move || {
+        App::new()
+            .app_data(Data::new(db_pool.clone()))
+            .app_data(Data::new(fleet.clone()))
+            .app_data(Data::new(dvar_allowlist.clone()))
+            .app_data(Data::new(console_policy.clone()))
+            .app_data(Data::new(admin_token.clone()))
+            .wrap(Logger::default())
+            .wrap(from_fn(metrics::track_http))
+            .route("/health", web::get().to(health))
+            .route("/metrics", web::get().to(metrics))
+            .route("/api/public/v1/maps", web::get().to(maps))
+            .route("/api/public/v1/gametypes", web::get().to(gametypes))
+            .route("/api/public/v1/gametypes/{tag}", web::get().to(gametype))
+            .route("/api/public/v1/servers", web::get().to(servers))
+            .route("/api/public/v1/health", web::get().to(fleet_health))
+            .route(
+                "/api/public/v1/servers/{server_id}/health",
+                web::get().to(health_of_server),
+            )
+            .route(
+                "/api/public/v1/players/{guid}/stats",
+                web::get().to(player_stats),
+            )
+            .route("/api/public/v1/matches", web::get().to(matches))
+            .route(
+                "/api/public/v1/leaderboards/{metric}",
+                web::get().to(leaderboard),
+            )
+            .route("/api/public/v1/matches/{id}", web::get().to(game_match))
+            .route("/api/public/v1/events", web::get().to(events_sse))
+            .route("/api/public/v1/events/ws", web::get().to(events_ws))
+            .route(
+                "/api/public/v1/servers/{server_id}/map_restart",
+                web::get().to(map_restart),
+            )
+            .route(
+                "/api/public/v1/servers/{server_id}/fast_restart",
+                web::get().to(fast_restart),
+            )
+            .route(
+                "/api/public/v1/servers/{server_id}/gametype_map",
+                web::post().to(gametype_map),
+            )
+            .route(
+                "/api/public/v1/servers/{server_id}/players",
+                web::get().to(players),
+            )
+            .route(
+                "/api/public/v1/servers/{server_id}/info",
+                web::get().to(info),
+            )
+            .route(
+                "/api/public/v1/servers/{server_id}/status",
+                web::get().to(status),
+            )
+            .service(
+                web::scope("/api/admin/v1")
+                    .wrap(from_fn(auth::require_admin))
+                    .configure(admin_routes),
+            )
+    })
+    .bind((http_host, http_port))?
+    .run()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fleet::tests::definition;
+    use actix_web::App;
+    use actix_web::test::{TestRequest, call_and_read_body_json, call_service, init_service};
+
+    fn test_fleet(port: u16) -> Fleet {
+        Fleet::new(vec![definition("test", port)], &Default::default()).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_map_restart() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/public/v1/servers/{server_id}/map_restart",
+            web::get().to(map_restart),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/test/map_restart")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(mock_server.payload_size().await, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_fast_restart() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/public/v1/servers/{server_id}/fast_restart",
+            web::get().to(fast_restart),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/test/fast_restart")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(mock_server.payload_size().await, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_map_rotate() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/map_rotate",
+            web::post().to(map_rotate),
+        ))
+        .await;
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/map_rotate")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(mock_server.payload_size().await, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_update_gametype_rename() {
+        let db_pool = db::create_pool("127.0.0.1", 1, None, None, "olg").unwrap();
+        let app = init_service(App::new().app_data(Data::new(db_pool)).route(
+            "/api/admin/v1/gametypes/{tag}",
+            web::put().to(update_gametype),
+        ))
+        .await;
+        let req = TestRequest::put()
+            .uri("/api/admin/v1/gametypes/war")
+            .set_json(&GametypeDefinition {
+                tag: "tdm".parse().unwrap(),
+                name: "Team Deathmatch".to_string(),
+                description: None,
+                team_based: true,
+                source_mod: None,
+            })
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    fn gametype_map_app(
+        db_pool: DBPool,
+        fleet: Fleet,
+    ) -> App<
+        impl actix_web::dev::ServiceFactory<
+            actix_web::dev::ServiceRequest,
+            Config = (),
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        App::new()
+            .app_data(Data::new(db_pool))
+            .app_data(Data::new(fleet))
+            .route(
+                "/api/public/v1/servers/{server_id}/gametype_map",
+                web::post().to(gametype_map),
+            )
+    }
+
+    fn gametype_map_request(gametype: &str, map: &str) -> TestRequest {
+        TestRequest::post()
+            .uri("/api/public/v1/servers/test/gametype_map")
+            .set_json(&GametypeMapRequest {
+                gametype: gametype.parse().unwrap(),
+                map: map.to_string(),
+            })
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a database"]
+    async fn test_gametype_map() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(gametype_map_app(crate::db::tests::test_pool(), fleet)).await;
+        let resp = call_service(&app, gametype_map_request("sd", "mp_crash").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+        assert_eq!(mock_server.payload_size().await, 3);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a database"]
+    async fn test_gametype_map_catalog() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let db_pool = crate::db::tests::test_pool();
+        let suffix = chrono::Utc::now().timestamp_micros();
+        let inactive = format!("mp_retired_{}", suffix);
+        let db_client = db_pool.get().await.unwrap();
+        db_client
+            .execute(
+                "INSERT INTO codmap (tag, active) VALUES ($1, false)",
+                &[&inactive],
+            )
+            .await
+            .unwrap();
+        let app = init_service(gametype_map_app(db_pool.clone(), fleet)).await;
+
+        let unknown = format!("mp_unknown_{}", suffix);
+        let resp = call_service(&app, gametype_map_request("sd", &unknown).to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let resp = call_service(&app, gametype_map_request("sd", &inactive).to_request()).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        // A stock gametype of another title
+        let ctf = GametypeDefinition {
+            tag: format!("ctf_{}", suffix).parse().unwrap(),
+            name: "Capture the Flag".to_string(),
+            description: None,
+            team_based: true,
+            source_mod: None,
+        };
+        db::create_gametype(&db_client, &ctf).await.unwrap();
+        let resp = call_service(
+            &app,
+            gametype_map_request(ctf.tag.to_tag(), "mp_crash").to_request(),
+        )
+        .await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(mock_server.payload_size().await, 0);
+
+        // A mod gametype added after the stock maps were
+        let gungame = GametypeDefinition {
+            tag: format!("gg_{}", suffix).parse().unwrap(),
+            name: "Gun Game".to_string(),
+            description: None,
+            team_based: false,
+            source_mod: Some("gungame".to_string()),
+        };
+        db::create_gametype(&db_client, &gungame).await.unwrap();
+        let resp = call_service(
+            &app,
+            gametype_map_request(gungame.tag.to_tag(), "mp_crash").to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+
+        db_client
+            .execute("DELETE FROM codmap WHERE tag = $1", &[&inactive])
+            .await
+            .unwrap();
+        db::delete_gametype(&db_client, &ctf.tag).await.unwrap();
+        db::delete_gametype(&db_client, &gungame.tag).await.unwrap();
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a database"]
+    async fn test_gametype_map_usermap() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let db_pool = crate::db::tests::test_pool();
+        let tag = format!("mp_usermap_{}", chrono::Utc::now().timestamp_micros());
+        let db_client = db_pool.get().await.unwrap();
+        db_client
+            .execute("INSERT INTO codmap (tag) VALUES ($1)", &[&tag])
+            .await
+            .unwrap();
+        let app = init_service(
+            gametype_map_app(db_pool.clone(), fleet)
+                .route("/api/public/v1/maps", web::get().to(maps)),
+        )
+        .await;
+
+        // Without any gametype listed the map takes them all
+        let resp = call_service(&app, gametype_map_request("sd", &tag).to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+        let req = TestRequest::get()
+            .uri(&format!("/api/public/v1/maps?gametype=koth&q={}", tag))
+            .to_request();
+        let maps: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(maps[0]["tag"], tag.as_str());
+
+        db_client
+            .execute("DELETE FROM codmap WHERE tag = $1", &[&tag])
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_gametype_map_missing() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let request = GametypeMapRequest {
+            gametype: "sd".parse().unwrap(),
+            map: "mp_missing".to_string(),
+        };
+        let error = change_gametype_map(&fleet, "test", &request)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            actix_web::ResponseError::status_code(&error),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_gametype_map_injection() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        // Rejected before the catalogs are looked at, no database needed
+        let db_pool = db::create_pool("127.0.0.1", 1, None, None, "olg").unwrap();
+        let app = init_service(gametype_map_app(db_pool, fleet)).await;
+        let resp = call_service(
+            &app,
+            gametype_map_request("sd", "mp_crash; rcon_password x").to_request(),
+        )
+        .await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(mock_server.payload_size().await, 0);
+    }
+
+    #[actix_web::test]
+    async fn test_players() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/public/v1/servers/{server_id}/players",
+            web::get().to(players),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/test/players")
+            .to_request();
+        let players: Vec<serde_json::Value> = call_and_read_body_json(&app, req).await;
+        assert_eq!(players.len(), 3);
+        assert!(players[0].get("name").is_some());
+        assert!(players[0].get("guid").is_none());
+        assert!(players[0].get("address").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_admin_players() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/players",
+            web::get().to(admin_players),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/admin/v1/servers/test/players")
+            .to_request();
+        let players: Vec<serde_json::Value> = call_and_read_body_json(&app, req).await;
+        assert_eq!(players.len(), 3);
+        assert_eq!(players[0]["guid"], "0a1b2c3d4e5f60718293a4b5c6d7e8f9");
+    }
+
+    #[actix_web::test]
+    async fn test_info() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/public/v1/servers/{server_id}/info",
+            web::get().to(info),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/test/info")
+            .to_request();
+        let info: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(info["map"], "mp_crash");
+        assert_eq!(info["clients"], 3);
+    }
+
+    #[actix_web::test]
+    async fn test_status() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/public/v1/servers/{server_id}/status",
+            web::get().to(status),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/test/status")
+            .to_request();
+        let status: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(status["info"]["gametype"], "war");
+        assert_eq!(status["players"].as_array().map(Vec::len), Some(3));
+    }
 
-async fn map_restart(cod_server: Data<CodServer>) -> Result<HttpResponse, ApiError> {
-    cod_server.map_restart().await?;
-    Ok(HttpResponse::NoContent().finish())
-}
+    #[actix_web::test]
+    async fn test_server_not_responding() {
+        let mock_server = crate::server::tests::MockCodServer::silent().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            crate::rcon::RconSettings {
+                timeout: std::time::Duration::from_millis(100),
+                retries: 0,
+                ..Default::default()
+            },
+        );
+        let mut fleet = Fleet::default();
+        fleet
+            .insert(definition("test", mock_server.port()), server)
+            .unwrap();
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/public/v1/servers/{server_id}/players",
+            web::get().to(players),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/test/players")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::GATEWAY_TIMEOUT);
+    }
 
-async fn fast_restart(cod_server: Data<CodServer>) -> Result<HttpResponse, ApiError> {
-    cod_server.fast_restart().await?;
-    Ok(HttpResponse::NoContent().finish())
-}
+    fn dvar_allowlist() -> DvarAllowlist {
+        serde_json::from_str(
+            r#"{
+                "sv_hostname": {"type": "string", "max_length": 32},
+                "sv_unknown": {"type": "string"},
+                "scr_sd_timelimit": {"writable": true, "type": "float", "min": 0, "max": 10}
+            }"#,
+        )
+        .unwrap()
+    }
 
-async fn gametype_map(
-    cod_server: Data<CodServer>,
-    request_body: web::Json<GametypeMapRequest>,
-) -> Result<HttpResponse, ApiError> {
-    cod_server
-        .gametype_map(&request_body.gametype, &request_body.map)
-        .await?;
-    Ok(HttpResponse::NoContent().finish())
-}
+    #[actix_web::test]
+    async fn test_get_dvar() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .app_data(Data::new(dvar_allowlist()))
+                .route(
+                    "/api/admin/v1/servers/{server_id}/dvars/{name}",
+                    web::get().to(get_dvar),
+                ),
+        )
+        .await;
 
-async fn health() -> impl Responder {
-    HttpResponse::NoContent().finish()
-}
+        let req = TestRequest::get()
+            .uri("/api/admin/v1/servers/test/dvars/sv_hostname")
+            .to_request();
+        let dvar: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(dvar["value"], "^1OLG ^7Public TDM");
 
-pub async fn run(
-    http_host: &str,
-    http_port: u16,
-    db_pool: DBPool,
-    cod_server: CodServer,
-) -> std::io::Result<()> {
-    HttpServer::new(move || {
-        App::new()
-            .app_data(Data::new(db_pool.clone()))
-            .app_data(Data::new(cod_server.clone()))
-            .wrap(Logger::default())
-            .route("/health", web::get().to(health))
-            .route("/api/public/v1/maps", web::get().to(maps))
-            .route(
-                "/api/public/v1/server/map_restart",
-                web::get().to(map_restart),
-            )
-            .route(
-                "/api/public/v1/server/fast_restart",
-                web::get().to(fast_restart),
-            )
-            .route(
-                "/api/public/v1/server/gametype_map",
-                web::post().to(gametype_map),
-            )
-    })
-    .bind((http_host, http_port))?
-    .run()
-    .await
-}
+        let req = TestRequest::get()
+            .uri("/api/admin/v1/servers/test/dvars/sv_unknown")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use actix_web::App;
-    use actix_web::test::{TestRequest, call_service, init_service};
+        let req = TestRequest::get()
+            .uri("/api/admin/v1/servers/test/dvars/rcon_password")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        assert_eq!(mock_server.payload_size().await, 2);
+    }
 
     #[actix_web::test]
-    async fn test_map_restart() {
+    async fn test_admin_token() {
         let mock_server = crate::server::tests::MockCodServer::new().await;
-        let server = CodServer::new("127.0.0.1", mock_server.port(), "test_password");
-        let app = init_service(App::new().app_data(Data::new(server)).route(
-            "/api/public/v1/server/map_restart",
-            web::get().to(map_restart),
-        ))
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .app_data(Data::new(dvar_allowlist()))
+                .app_data(Data::new(AdminToken::new(Some("s3cret".to_string()))))
+                .service(
+                    web::scope("/api/admin/v1")
+                        .wrap(from_fn(auth::require_admin))
+                        .configure(admin_routes),
+                ),
+        )
         .await;
+
+        for authorization in [None, Some("Bearer wrong"), Some("s3cret")] {
+            let mut req = TestRequest::get().uri("/api/admin/v1/servers/test/dvars/sv_hostname");
+            if let Some(authorization) = authorization {
+                req = req.insert_header(("Authorization", authorization));
+            }
+            let resp = call_service(&app, req.to_request()).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(mock_server.payload_size().await, 0);
+
         let req = TestRequest::get()
-            .uri("/api/public/v1/server/map_restart")
+            .uri("/api/admin/v1/servers/test/dvars/sv_hostname")
+            .insert_header(("Authorization", "Bearer s3cret"))
+            .to_request();
+        let dvar: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(dvar["value"], "^1OLG ^7Public TDM");
+    }
+
+    #[actix_web::test]
+    async fn test_set_dvar() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .app_data(Data::new(dvar_allowlist()))
+                .route(
+                    "/api/admin/v1/servers/{server_id}/dvars/{name}",
+                    web::put().to(set_dvar),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::put()
+            .uri("/api/admin/v1/servers/test/dvars/sv_hostname")
+            .set_json(&DvarRequest {
+                value: "New name".to_string(),
+            })
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        let req = TestRequest::put()
+            .uri("/api/admin/v1/servers/test/dvars/scr_sd_timelimit")
+            .set_json(&DvarRequest {
+                value: "42".to_string(),
+            })
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(mock_server.payload_size().await, 0);
+
+        let req = TestRequest::put()
+            .uri("/api/admin/v1/servers/test/dvars/scr_sd_timelimit")
+            .set_json(&DvarRequest {
+                value: "2.5".to_string(),
+            })
+            .to_request();
+        let dvar: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(dvar["value"], "2.5");
+    }
+
+    #[actix_web::test]
+    async fn test_kick() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/kick",
+            web::post().to(kick),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/kick")
+            .set_json(PlayerTarget::Name("Big Bad Wolf".to_string()))
+            .to_request();
+        let player: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(player["slot"], 1);
+        assert_eq!(player["guid"], "f9e8d7c6b5a4938271605f4e3d2c1b0a");
+
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/kick")
+            .set_json(PlayerTarget::Slot(42))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_unban() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/unban",
+            web::post().to(unban),
+        ))
+        .await;
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/unban")
+            .set_json(&UnbanRequest {
+                name: "Sardy".to_string(),
+            })
             .to_request();
         let resp = call_service(&app, req).await;
         assert!(resp.status().is_success());
@@ -117,16 +2037,20 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_fast_restart() {
+    async fn test_say() {
         let mock_server = crate::server::tests::MockCodServer::new().await;
-        let server = CodServer::new("127.0.0.1", mock_server.port(), "test_password");
-        let app = init_service(App::new().app_data(Data::new(server)).route(
-            "/api/public/v1/server/fast_restart",
-            web::get().to(fast_restart),
-        ))
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .route("/api/admin/v1/servers/{server_id}/say", web::post().to(say)),
+        )
         .await;
-        let req = TestRequest::get()
-            .uri("/api/public/v1/server/fast_restart")
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/say")
+            .set_json(&MessageRequest {
+                message: "Server restart in 5 minutes".to_string(),
+            })
             .to_request();
         let resp = call_service(&app, req).await;
         assert!(resp.status().is_success());
@@ -134,26 +2058,491 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_gametype_map() {
+    async fn test_announce_unknown_command() {
         let mock_server = crate::server::tests::MockCodServer::new().await;
-        let server = CodServer::new("127.0.0.1", mock_server.port(), "test_password");
-        let app = init_service(App::new().app_data(Data::new(server)).route(
-            "/api/public/v1/server/gametype_map",
-            web::post().to(gametype_map),
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/announce",
+            web::post().to(announce),
         ))
         .await;
         let req = TestRequest::post()
-            .uri("/api/public/v1/server/gametype_map")
-            .set_json(&GametypeMapRequest {
-                gametype: Gametype::SearchAndDestroy,
-                map: "mp_crash".to_string(),
+            .uri("/api/admin/v1/servers/test/announce")
+            .set_json(&MessageRequest {
+                message: "Server restart in 5 minutes".to_string(),
             })
             .to_request();
         let resp = call_service(&app, req).await;
-        assert!(resp.status().is_success());
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_say_empty() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .route("/api/admin/v1/servers/{server_id}/say", web::post().to(say)),
+        )
+        .await;
+        for message in ["", "   ", "^1 ^7"] {
+            let req = TestRequest::post()
+                .uri("/api/admin/v1/servers/test/say")
+                .set_json(&MessageRequest {
+                    message: message.to_string(),
+                })
+                .to_request();
+            let resp = call_service(&app, req).await;
+            assert_eq!(resp.status(), 422);
+        }
+        assert_eq!(mock_server.payload_size().await, 0);
+    }
+
+    fn console_app(
+        db_pool: DBPool,
+        fleet: Fleet,
+    ) -> App<
+        impl actix_web::dev::ServiceFactory<
+            actix_web::dev::ServiceRequest,
+            Config = (),
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        App::new()
+            .app_data(Data::new(db_pool))
+            .app_data(Data::new(fleet))
+            .app_data(Data::new(ConsolePolicy::default()))
+            .route(
+                "/api/admin/v1/servers/{server_id}/console",
+                web::post().to(console),
+            )
+            .route("/api/admin/v1/console", web::get().to(console_log))
+    }
+
+    #[actix_web::test]
+    async fn test_console_unrecorded() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let db_pool = db::create_pool("127.0.0.1", 1, None, None, "olg").unwrap();
+        let app = init_service(console_app(db_pool, test_fleet(mock_server.port()))).await;
+        for command in ["status", "quit"] {
+            let req = TestRequest::post()
+                .uri("/api/admin/v1/servers/test/console")
+                .set_json(&ConsoleRequest {
+                    command: command.to_string(),
+                })
+                .to_request();
+            let resp = call_service(&app, req).await;
+            assert_eq!(
+                resp.status(),
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+            );
+        }
+        assert_eq!(mock_server.payload_size().await, 0);
+    }
+
+    #[actix_web::test]
+    async fn test_console_too_long() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        // Rejected before the audit log is written, no database needed
+        let db_pool = db::create_pool("127.0.0.1", 1, None, None, "olg").unwrap();
+        let app = init_service(console_app(db_pool, test_fleet(mock_server.port()))).await;
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/console")
+            .set_json(&ConsoleRequest {
+                command: format!("say {}", "a".repeat(MAX_CONSOLE_COMMAND_LENGTH)),
+            })
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(mock_server.payload_size().await, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a database"]
+    async fn test_console() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let server_id = format!("console-{}", chrono::Utc::now().timestamp_micros());
+        let fleet = Fleet::new(
+            vec![definition(&server_id, mock_server.port())],
+            &Default::default(),
+        )
+        .unwrap();
+        let mut receiver = fleet.events().subscribe();
+        let app = init_service(console_app(crate::db::tests::test_pool(), fleet)).await;
+
+        let req = TestRequest::post()
+            .uri(&format!("/api/admin/v1/servers/{}/console", server_id))
+            .set_json(&ConsoleRequest {
+                command: "status".to_string(),
+            })
+            .to_request();
+        let response: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(response["reply"], crate::models::STATUS_FIXTURE);
+
+        let req = TestRequest::post()
+            .uri(&format!("/api/admin/v1/servers/{}/console", server_id))
+            .set_json(&ConsoleRequest {
+                command: "set \"g_password\" secret".to_string(),
+            })
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        assert_eq!(mock_server.payload_size().await, 1);
+
+        // Both commands are audited, newest first, arguments included
+        let req = TestRequest::get()
+            .uri(&format!("/api/admin/v1/console?server={}", server_id))
+            .to_request();
+        let log: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(log["total"], 2);
+        assert_eq!(log["items"][0]["command"], "set \"g_password\" secret");
+        assert_eq!(log["items"][0]["allowed"], false);
+        assert_eq!(log["items"][0]["reply"], serde_json::Value::Null);
+        assert_eq!(log["items"][1]["command"], "status");
+        assert_eq!(log["items"][1]["allowed"], true);
+        assert_eq!(log["items"][1]["reply"], crate::models::STATUS_FIXTURE);
+
+        // The feed only gets the name of the commands
+        let targets = [receiver.try_recv().unwrap(), receiver.try_recv().unwrap()].map(|event| {
+            match event.event {
+                GameEvent::AdminAction(action) => action.target,
+                event => panic!("not an admin action: {:?}", event),
+            }
+        });
+        assert_eq!(
+            targets,
+            [Some("status".to_string()), Some("set".to_string())]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_tell() {
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        let fleet = test_fleet(mock_server.port());
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/tell",
+            web::post().to(tell),
+        ))
+        .await;
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/tell")
+            .set_json(&TellRequest {
+                target: PlayerTarget::Guid("0a1b2c3d4e5f60718293a4b5c6d7e8f9".to_string()),
+                message: "Welcome back".to_string(),
+            })
+            .to_request();
+        let player: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(player["slot"], 0);
         assert_eq!(mock_server.payload_size().await, 2);
     }
 
+    #[actix_web::test]
+    async fn test_action_reported() {
+        let mock_server = crate::server::tests::MockCodServer::silent().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            crate::rcon::RconSettings {
+                timeout: std::time::Duration::from_millis(100),
+                ..Default::default()
+            },
+        );
+        let mut fleet = Fleet::default();
+        fleet
+            .insert(definition("test", mock_server.port()), server)
+            .unwrap();
+        let mut receiver = fleet.events().subscribe();
+        let app = init_service(App::new().app_data(Data::new(fleet)).route(
+            "/api/admin/v1/servers/{server_id}/map_rotate",
+            web::post().to(map_rotate),
+        ))
+        .await;
+        let req = TestRequest::post()
+            .uri("/api/admin/v1/servers/test/map_rotate")
+            .to_request();
+        call_service(&app, req).await;
+
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.server_id, "test");
+        let crate::models::GameEvent::AdminAction(action) = event.event else {
+            panic!("not an admin action: {:?}", event.event);
+        };
+        assert_eq!(action.action, "map_rotate");
+        assert_eq!(action.error, Some("Game server not responding".to_string()));
+    }
+
+    fn server_event(server_id: &str, event: crate::models::GameEvent) -> ServerEvent {
+        ServerEvent {
+            server_id: server_id.to_string(),
+            time: chrono::Utc::now(),
+            uptime: Some(42),
+            log_file: None,
+            offset: None,
+            event,
+        }
+    }
+
+    #[test]
+    fn test_feed_filter() {
+        let kill = server_event("tdm", crate::models::GameEvent::ExitLevel);
+        assert!(FeedFilter::default().matches(&kill));
+        let filter = FeedFilter {
+            servers: Some("sd, tdm".to_string()),
+            types: Some("exit_level".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&kill));
+        assert!(!filter.matches(&server_event("scrim", crate::models::GameEvent::ExitLevel)));
+        assert!(!filter.matches(&server_event("tdm", crate::models::GameEvent::ShutdownGame)));
+
+        let action = server_event(
+            "tdm",
+            GameEvent::AdminAction(AdminAction {
+                action: "kick".to_string(),
+                target: Some("slot 1".to_string()),
+                error: None,
+            }),
+        );
+        assert!(!FeedFilter::default().matches(&action));
+        let filter = FeedFilter {
+            admin: true,
+            ..Default::default()
+        };
+        assert!(filter.matches(&action));
+    }
+
+    #[test]
+    fn test_maps_query() {
+        let (filter, per_page) = MapsQuery::default().into_filter().unwrap();
+        assert_eq!(filter, MapFilter::default());
+        assert_eq!(per_page, None);
+
+        let query = MapsQuery {
+            cursor: Some("mp_crash".to_string()),
+            ..Default::default()
+        };
+        let (_, per_page) = query.into_filter().unwrap();
+        assert_eq!(per_page, Some(DEFAULT_PAGE_SIZE));
+
+        let query = MapsQuery {
+            q: Some("  ".to_string()),
+            sort: Some(MapSort::Name),
+            per_page: Some(MAX_PAGE_SIZE),
+            ..Default::default()
+        };
+        let (filter, per_page) = query.into_filter().unwrap();
+        assert_eq!(filter.search, None);
+        assert_eq!(filter.sort, MapSort::Name);
+        assert_eq!(per_page, Some(MAX_PAGE_SIZE));
+
+        for per_page in [0, MAX_PAGE_SIZE + 1] {
+            let query = MapsQuery {
+                per_page: Some(per_page),
+                ..Default::default()
+            };
+            assert!(matches!(
+                query.into_filter(),
+                Err(ApiError::Unprocessable(_))
+            ));
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a database"]
+    async fn test_check_rotation_entries() {
+        let db_pool = crate::db::tests::test_pool();
+        let mut db_client = db_pool.get().await.unwrap();
+        let suffix = chrono::Utc::now().timestamp_micros();
+        let mut created = Vec::new();
+        for (tag, active) in [("mp_sd_only", true), ("mp_retired", false)] {
+            let settings = MapSettings {
+                tag: format!("{}_{}", tag, suffix),
+                name: None,
+                original: false,
+                active,
+                seq: 0,
+                gametypes: vec!["sd".parse().unwrap()],
+            };
+            created.push(db::create_map(&mut db_client, &settings).await.unwrap());
+        }
+        let entry = |gametype: &str, map: &str| RotationEntry {
+            gametype: gametype.parse().unwrap(),
+            map: map.to_string(),
+        };
+        let sd_only = format!("mp_sd_only_{}", suffix);
+        let retired = format!("mp_retired_{}", suffix);
+
+        let valid = [entry("war", "mp_crash"), entry("sd", &sd_only)];
+        assert!(check_rotation_entries(&db_client, &valid).await.is_ok());
+        for invalid in [
+            [entry("war", "mp_crash"), entry("war", &sd_only)],
+            [entry("sd", &retired), entry("war", "mp_crash")],
+            [entry("war", "mp_crash"), entry("war", "mp_nowhere")],
+        ] {
+            assert!(matches!(
+                check_rotation_entries(&db_client, &invalid).await,
+                Err(ApiError::Unprocessable(_))
+            ));
+        }
+
+        for id in created {
+            db::delete_map(&db_client, &id).await.unwrap();
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a database"]
+    async fn test_maps() {
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(crate::db::tests::test_pool()))
+                .route("/api/public/v1/maps", web::get().to(maps)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/maps?original=true")
+            .to_request();
+        let all: serde_json::Value = call_and_read_body_json(&app, req).await;
+        let all = all.as_array().unwrap();
+        assert!(all.len() > 2);
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/maps?original=true&per_page=2")
+            .to_request();
+        let page: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(page["items"].as_array().map(Vec::len), Some(2));
+        assert_eq!(page["next_cursor"], all[1]["tag"]);
+
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/api/public/v1/maps?original=true&cursor={}",
+                page["next_cursor"].as_str().unwrap()
+            ))
+            .to_request();
+        let page: serde_json::Value = call_and_read_body_json(&app, req).await;
+        assert_eq!(page["items"][0], all[2]);
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/maps?cursor=mp_nowhere")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn test_page_bounds() {
+        assert_eq!(
+            PageQuery::default().bounds().unwrap(),
+            (1, DEFAULT_PAGE_SIZE)
+        );
+        let query = PageQuery {
+            page: Some(3),
+            per_page: Some(50),
+            server: None,
+        };
+        assert_eq!(query.bounds().unwrap(), (3, 50));
+        for (page, per_page) in [(0, 10), (1, 0), (1, MAX_PAGE_SIZE + 1)] {
+            let query = PageQuery {
+                page: Some(page),
+                per_page: Some(per_page),
+                server: None,
+            };
+            assert!(matches!(query.bounds(), Err(ApiError::Unprocessable(_))));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_events_sse() {
+        use actix_web::body::MessageBody;
+
+        let fleet = Fleet::new(
+            vec![definition("tdm", 28960), definition("sd", 28961)],
+            &Default::default(),
+        )
+        .unwrap();
+        let events = fleet.events().clone();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .route("/api/public/v1/events", web::get().to(events_sse)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/events?servers=scrim")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/events?servers=sd&types=shutdown_game")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let mut body = std::pin::pin!(resp.into_body());
+
+        events.publish(server_event("tdm", crate::models::GameEvent::ShutdownGame));
+        events.publish(server_event("sd", crate::models::GameEvent::ExitLevel));
+        events.publish(server_event("sd", crate::models::GameEvent::ShutdownGame));
+
+        let chunk = futures_util::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        let frame = String::from_utf8_lossy(&chunk).to_string();
+        assert!(frame.starts_with("event: shutdown_game\ndata: {"));
+        assert!(frame.contains(r#""server_id":"sd""#));
+        assert!(frame.ends_with("\n\n"));
+    }
+
+    #[actix_web::test]
+    async fn test_servers() {
+        let fleet = Fleet::new(
+            vec![definition("tdm", 28960), definition("scrim", 28962)],
+            &Default::default(),
+        )
+        .unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(fleet))
+                .route("/api/public/v1/servers", web::get().to(servers))
+                .route(
+                    "/api/public/v1/servers/{server_id}/players",
+                    web::get().to(players),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers")
+            .to_request();
+        let servers: Vec<serde_json::Value> = call_and_read_body_json(&app, req).await;
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0]["id"], "scrim");
+        assert_eq!(servers[1]["port"], 28960);
+        assert!(servers[0].get("rcon_password").is_none());
+
+        let req = TestRequest::get()
+            .uri("/api/public/v1/servers/sd/players")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_web::test]
     async fn test_health() {
         let app = init_service(App::new().route("/health", web::get().to(health))).await;
@@ -161,4 +2550,30 @@ mod tests {
         let resp = call_service(&app, req).await;
         assert!(resp.status().is_success());
     }
+
+    #[actix_web::test]
+    async fn test_metrics() {
+        let db_pool = db::create_pool("127.0.0.1", 5432, None, None, "olg").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(db_pool))
+                .app_data(Data::new(Fleet::default()))
+                .wrap(from_fn(metrics::track_http))
+                .route("/health", web::get().to(health))
+                .route("/metrics", web::get().to(metrics)),
+        )
+        .await;
+        let req = TestRequest::get().uri("/health").to_request();
+        call_service(&app, req).await;
+
+        let req = TestRequest::get().uri("/metrics").to_request();
+        let body = actix_web::test::call_and_read_body(&app, req).await;
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(
+            text.contains(
+                "olg_http_requests_total{method=\"GET\",route=\"/health\",status=\"204\"}"
+            )
+        );
+        assert!(text.contains("olg_db_pool_max_size"));
+    }
 }
@@ -15,18 +15,31 @@
  *
  */
 
-use crate::server::CodServer;
+use crate::fleet::{Fleet, ServerDefinition};
+use crate::gamelog::GameEvents;
+use crate::rcon::RconSettings;
 use std::env;
-use tracing::{Level, info};
+use std::time::Duration;
+use tracing::{Level, debug, info, warn};
 
+mod auth;
+mod command;
 mod config;
 mod db;
 mod error;
+mod fleet;
+mod game;
+mod gamelog;
 mod http;
 mod log;
 mod maps;
+mod matches;
+mod metrics;
 mod models;
+mod monitor;
+mod rcon;
 mod server;
+mod stats;
 mod ui;
 
 #[tokio::main]
@@ -36,7 +49,7 @@ async fn main() -> anyhow::Result<()> {
     let log_level: Level = env::var("LOG_LEVEL")
         .unwrap_or_default()
         .parse()
-        .unwrap_or_else(|_| Level::WARN);
+        .unwrap_or(Level::WARN);
     log::configure(log_level);
 
     info!("Parsing configuration file");
@@ -51,11 +64,68 @@ async fn main() -> anyhow::Result<()> {
         &configuration.db_name,
     )?;
 
-    info!("Creating server");
-    let cod_server = CodServer::new(
-        &configuration.server_host,
-        configuration.server_port,
-        &configuration.server_rconpassword,
+    info!("Loading game servers");
+    let mut servers = configuration.servers;
+    // The database only adds to the configured servers, those keep working
+    // while it is down or not migrated yet
+    match load_servers(&db_pool).await {
+        Ok(db_servers) => servers.extend(db_servers),
+        Err(error) => warn!("Game servers not loaded from the database: {}", error),
+    }
+    if servers.is_empty() {
+        // Single server setups keep working with the old variables alone
+        servers.push(ServerDefinition {
+            id: "default".to_string(),
+            name: "Default".to_string(),
+            host: configuration.server_host,
+            port: configuration.server_port,
+            game: configuration.server_game,
+            rcon_password: configuration.server_rconpassword,
+            log_path: configuration.server_log_path,
+        });
+    }
+    let fleet = Fleet::new(
+        servers,
+        &RconSettings {
+            timeout: Duration::from_millis(configuration.server_rcon_timeout),
+            retries: configuration.server_rcon_retries,
+            quiet_period: Duration::from_millis(configuration.server_rcon_quiet_period),
+            max_response_size: configuration.server_rcon_max_response_size,
+            min_interval: Duration::from_millis(configuration.server_rcon_min_interval),
+        },
+    )?;
+    info!("Managing {} game servers", fleet.definitions().len());
+
+    info!("Starting game log tailers");
+    for definition in fleet.definitions() {
+        if let Some(log_path) = &definition.log_path {
+            gamelog::spawn_tailer(
+                fleet.events().clone(),
+                &definition.id,
+                log_path,
+                Duration::from_millis(configuration.server_log_poll_interval),
+                configuration.server_log_replay,
+            );
+        }
+    }
+    tokio::spawn(trace_game_events(fleet.events().clone()));
+    stats::spawn_recorder(
+        fleet.events(),
+        db_pool.clone(),
+        chrono::Duration::days(configuration.server_log_retention_days),
+    );
+    matches::spawn_recorder(fleet.events(), db_pool.clone());
+
+    if !configuration.admin_token.is_set() {
+        warn!("ADMIN_TOKEN is not set, the admin API refuses every request");
+    }
+
+    info!("Starting health monitor");
+    monitor::spawn_monitor(
+        fleet.clone(),
+        db_pool.clone(),
+        Duration::from_millis(configuration.server_health_interval),
+        chrono::Duration::days(configuration.server_health_retention_days),
     );
 
     tokio::select! {
@@ -63,7 +133,10 @@ async fn main() -> anyhow::Result<()> {
             &configuration.http_bind_host,
             configuration.http_bind_port,
             db_pool,
-            cod_server
+            fleet,
+            configuration.server_dvars,
+            configuration.server_console,
+            configuration.admin_token,
         ) => result?,
         result = shutdown_signal() => result?,
     }
@@ -71,6 +144,23 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn load_servers(db_pool: &db::DBPool) -> anyhow::Result<Vec<ServerDefinition>> {
+    db::get_servers(&db_pool.get().await?).await
+}
+
+async fn trace_game_events(game_events: GameEvents) {
+    let mut receiver = game_events.subscribe();
+    loop {
+        match receiver.recv().await {
+            Ok(event) => debug!("{} event from {}", event.event.kind(), event.server_id),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                debug!("Skipped {} game events", skipped)
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
 async fn shutdown_signal() -> anyhow::Result<()> {
     tokio::signal::ctrl_c().await?;
     info!("Shutdown signal received. Exiting...");
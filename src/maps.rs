@@ -15,19 +15,163 @@
  *
  */
 
+use crate::models::Gametype;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+// Usermaps may have no display name yet
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub(crate) struct Map {
+    id: Uuid,
     tag: String,
-    name: String,
+    name: Option<String>,
+    original: bool,
+    seq: i32,
 }
 
 impl Map {
-    pub(crate) fn new(tag: &str, name: &str) -> Self {
+    pub(crate) fn new(id: Uuid, tag: &str, name: Option<&str>, original: bool, seq: i32) -> Self {
         Self {
+            id,
             tag: tag.to_string(),
-            name: name.to_string(),
+            name: name.map(str::to_string),
+            original,
+            seq,
         }
     }
+
+    pub(crate) fn tag(&self) -> &str {
+        &self.tag
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MapSort {
+    #[default]
+    Seq,
+    Name,
+}
+
+impl MapSort {
+    // Ties are broken by the tag, which is unique and works as cursor.
+    // Maps without a name sort by their tag.
+    pub(crate) fn to_sql(self, alias: &str) -> String {
+        match self {
+            MapSort::Seq => format!("{0}.seq, {0}.tag", alias),
+            MapSort::Name => format!("COALESCE({0}.name, {0}.tag), {0}.tag", alias),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct MapFilter {
+    // Maps without any gametype listed support them all
+    pub(crate) gametype: Option<Gametype>,
+    pub(crate) search: Option<String>,
+    pub(crate) original: Option<bool>,
+    pub(crate) sort: MapSort,
+    // Tag of the last map of the previous page
+    pub(crate) cursor: Option<String>,
+}
+
+impl MapFilter {
+    // ILIKE pattern matching the search anywhere, tags are full of
+    // underscores so wildcards are escaped
+    pub(crate) fn pattern(&self) -> Option<String> {
+        self.search.as_ref().map(|search| {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+    }
+}
+
+fn default_active() -> bool {
+    true
+}
+
+// Everything about a catalog map an admin can change. A PUT replaces the
+// whole row, gametypes included.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct MapSettings {
+    pub(crate) tag: String,
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    #[serde(default)]
+    pub(crate) original: bool,
+    #[serde(default = "default_active")]
+    pub(crate) active: bool,
+    #[serde(default)]
+    pub(crate) seq: i32,
+    #[serde(default)]
+    pub(crate) gametypes: Vec<Gametype>,
+}
+
+// Catalog row as seen by the admin API, retired maps and usermaps without a
+// display name included
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct CatalogMap {
+    pub(crate) id: Uuid,
+    #[serde(flatten)]
+    pub(crate) settings: MapSettings,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_pattern() {
+        assert_eq!(MapFilter::default().pattern(), None);
+        let filter = MapFilter {
+            search: Some("mp_crash%".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.pattern().unwrap(), "%mp\\_crash\\%%");
+    }
+
+    #[test]
+    fn test_map_sort() {
+        assert_eq!(MapSort::default(), MapSort::Seq);
+        assert_eq!(
+            serde_json::from_str::<MapSort>("\"name\"").unwrap(),
+            MapSort::Name
+        );
+        assert!(serde_json::from_str::<MapSort>("\"tag\"").is_err());
+        assert_eq!(MapSort::Seq.to_sql("m"), "m.seq, m.tag");
+    }
+
+    #[test]
+    fn test_map_settings_defaults() {
+        let settings: MapSettings = serde_json::from_str(r#"{"tag": "mp_killhouse_v2"}"#).unwrap();
+        assert_eq!(settings.tag, "mp_killhouse_v2");
+        assert_eq!(settings.name, None);
+        assert!(!settings.original);
+        assert!(settings.active);
+        assert_eq!(settings.seq, 0);
+        assert!(settings.gametypes.is_empty());
+    }
+
+    #[test]
+    fn test_catalog_map_serialization() {
+        let map = CatalogMap {
+            id: Uuid::nil(),
+            settings: MapSettings {
+                tag: "mp_crash".to_string(),
+                name: Some("Crash".to_string()),
+                original: true,
+                active: true,
+                seq: -1,
+                gametypes: vec!["war".parse().unwrap(), "sd".parse().unwrap()],
+            },
+        };
+        let json = serde_json::to_value(&map).unwrap();
+        assert_eq!(json["id"], Uuid::nil().to_string());
+        assert_eq!(json["tag"], "mp_crash");
+        assert_eq!(json["gametypes"], serde_json::json!(["war", "sd"]));
+        assert_eq!(serde_json::from_value::<CatalogMap>(json).unwrap(), map);
+    }
 }
@@ -0,0 +1,319 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::db::{self, DBPool};
+use crate::gamelog::{GameEvents, ServerEvent};
+use crate::models::{Combatant, GameEvent, Gametype, MatchPlayer};
+use crate::stats::{self, StatsTracker};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+// A map played from its InitGame to its ShutdownGame. The InitGame line, and
+// its position in the log, identify the match when it is recorded.
+pub struct FinishedMatch {
+    pub server_id: String,
+    pub log_file: String,
+    pub log_offset: i64,
+    pub line: String,
+    pub map: String,
+    pub gametype: Gametype,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration: i64,
+    pub allies_kills: i64,
+    pub axis_kills: i64,
+    pub winner: Option<String>,
+    pub players: Vec<MatchPlayer>,
+}
+
+struct OpenMatch {
+    log_file: String,
+    log_offset: i64,
+    line: String,
+    uptime: u32,
+    map: String,
+    gametype: Gametype,
+    started_at: DateTime<Utc>,
+    allies_kills: i64,
+    axis_kills: i64,
+    winner: Option<String>,
+    players: BTreeMap<String, MatchPlayer>,
+}
+
+impl OpenMatch {
+    fn player(&mut self, guid: &str, name: &str) -> &mut MatchPlayer {
+        self.players
+            .entry(guid.to_lowercase())
+            .or_insert_with(|| MatchPlayer::new(guid, name))
+    }
+
+    fn set_team(&mut self, combatant: &Combatant) {
+        if stats::is_tracked(&combatant.guid) {
+            self.player(&combatant.guid, &combatant.name).team = combatant.team.clone();
+        }
+    }
+
+    fn set_outcome(&mut self, combatant: &Combatant, won: bool) {
+        self.set_team(combatant);
+        if stats::is_tracked(&combatant.guid) {
+            self.player(&combatant.guid, &combatant.name).won = Some(won);
+        }
+        if won {
+            self.winner = Some(if combatant.team.is_empty() {
+                combatant.guid.to_lowercase()
+            } else {
+                combatant.team.clone()
+            });
+        }
+    }
+
+    // Both ends are lines of the same log, so the uptime tells how long the
+    // match lasted better than the times they were read at
+    fn finish(self, event: &ServerEvent, uptime: u32) -> FinishedMatch {
+        let duration = stats::elapsed(self.uptime, uptime)
+            .map(i64::from)
+            .unwrap_or_else(|| (event.time - self.started_at).num_seconds());
+        FinishedMatch {
+            server_id: event.server_id.clone(),
+            log_file: self.log_file,
+            log_offset: self.log_offset,
+            line: self.line,
+            map: self.map,
+            gametype: self.gametype,
+            started_at: self.started_at,
+            ended_at: self.started_at + chrono::Duration::seconds(duration),
+            duration,
+            allies_kills: self.allies_kills,
+            axis_kills: self.axis_kills,
+            winner: self.winner,
+            players: self.players.into_values().collect(),
+        }
+    }
+}
+
+// Follows the open match of every server. Player lines are built from the
+// same increments as the overall statistics, team kills from the kills
+// between members of opposite teams. They are the team score in war only,
+// the other team gametypes score rounds or objectives.
+#[derive(Default)]
+pub struct MatchTracker {
+    stats: StatsTracker,
+    open: HashMap<String, OpenMatch>,
+}
+
+impl MatchTracker {
+    pub fn apply(&mut self, event: &ServerEvent) -> Option<FinishedMatch> {
+        let (Some(uptime), Some(log_file), Some(offset)) =
+            (event.uptime, &event.log_file, event.offset)
+        else {
+            return None;
+        };
+
+        // Sessions closed by InitGame and ShutdownGame still belong to the
+        // match being closed, so increments go in before anything else.
+        let increments = self.stats.apply(event);
+        if let Some(open) = self.open.get_mut(&event.server_id) {
+            for stats in &increments {
+                open.player(&stats.guid, &stats.name).add(stats);
+            }
+        }
+
+        match &event.event {
+            GameEvent::InitGame(info) => {
+                // A map without a ShutdownGame never ended properly
+                if self.open.remove(&event.server_id).is_some() {
+                    debug!("Dropping unfinished match of {}", event.server_id);
+                }
+                match info.gametype.parse::<Gametype>() {
+                    Ok(gametype) => {
+                        let open = OpenMatch {
+                            log_file: log_file.clone(),
+                            log_offset: i64::try_from(offset).ok()?,
+                            line: serde_json::to_string(&(uptime, &event.event)).ok()?,
+                            uptime,
+                            map: info.map.clone(),
+                            gametype,
+                            started_at: event.time,
+                            allies_kills: 0,
+                            axis_kills: 0,
+                            winner: None,
+                            players: BTreeMap::new(),
+                        };
+                        self.open.insert(event.server_id.clone(), open);
+                    }
+                    Err(error) => debug!("Not following match of {}: {}", event.server_id, error),
+                }
+                None
+            }
+            GameEvent::Kill(hit) | GameEvent::Damage(hit) => {
+                let open = self.open.get_mut(&event.server_id)?;
+                open.set_team(&hit.victim);
+                if let Some(attacker) = &hit.attacker {
+                    open.set_team(attacker);
+                    if matches!(event.event, GameEvent::Kill(_)) && attacker.team != hit.victim.team
+                    {
+                        match attacker.team.as_str() {
+                            "allies" => open.allies_kills += 1,
+                            "axis" => open.axis_kills += 1,
+                            _ => {}
+                        }
+                    }
+                }
+                None
+            }
+            GameEvent::Win(combatant) => {
+                self.open
+                    .get_mut(&event.server_id)?
+                    .set_outcome(combatant, true);
+                None
+            }
+            GameEvent::Loss(combatant) => {
+                self.open
+                    .get_mut(&event.server_id)?
+                    .set_outcome(combatant, false);
+                None
+            }
+            GameEvent::ShutdownGame => self
+                .open
+                .remove(&event.server_id)
+                .map(|open| open.finish(event, uptime)),
+            _ => None,
+        }
+    }
+}
+
+// Matches are identified by their InitGame line, so recording the same log
+// twice does not duplicate them.
+pub fn spawn_recorder(events: &GameEvents, db_pool: DBPool) -> tokio::task::JoinHandle<()> {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        let mut tracker = MatchTracker::default();
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Match history missed {} game events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(finished) = tracker.apply(&event) else {
+                continue;
+            };
+            if let Err(error) = record(&db_pool, &finished).await {
+                warn!("Error recording match of {}: {}", finished.server_id, error);
+            }
+        }
+    })
+}
+
+async fn record(db_pool: &DBPool, finished: &FinishedMatch) -> anyhow::Result<()> {
+    let mut db_client = db_pool.get().await?;
+    if let Some(id) = db::record_match(&mut db_client, finished).await? {
+        debug!("Recorded match {} of {}", id, finished.server_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GAMES_MP_LOG_FIXTURE, LogEntry};
+
+    fn apply(tracker: &mut MatchTracker, log: &str) -> Vec<FinishedMatch> {
+        log.lines()
+            .filter_map(|line| line.parse::<LogEntry>().ok())
+            .enumerate()
+            .filter_map(|(offset, entry)| {
+                tracker.apply(&ServerEvent {
+                    server_id: "tdm".to_string(),
+                    time: Utc::now(),
+                    uptime: Some(entry.uptime),
+                    log_file: Some("test".to_string()),
+                    offset: Some(offset as u64),
+                    event: entry.event,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fixture_match() {
+        let finished = apply(&mut MatchTracker::default(), GAMES_MP_LOG_FIXTURE);
+        assert_eq!(finished.len(), 1);
+
+        let finished = &finished[0];
+        assert_eq!(finished.map, "mp_crash");
+        assert_eq!(finished.gametype.to_tag(), "war");
+        assert_eq!(finished.log_offset, 0);
+        assert_eq!(finished.duration, 605);
+        // Every line has been read at about the same time
+        assert_eq!((finished.ended_at - finished.started_at).num_seconds(), 605);
+        assert_eq!(finished.allies_kills, 1);
+        assert_eq!(finished.axis_kills, 0);
+        assert_eq!(finished.winner.as_deref(), Some("allies"));
+        assert_eq!(finished.players.len(), 2);
+
+        let sardy = &finished.players[0];
+        assert_eq!(sardy.stats.guid, "0a1b2c3d4e5f60718293a4b5c6d7e8f9");
+        assert_eq!(sardy.team, "allies");
+        assert_eq!(sardy.stats.kills, 1);
+        assert_eq!(sardy.stats.suicides, 1);
+        assert_eq!(sardy.stats.time_played, 602);
+        assert_eq!(sardy.score, 0);
+        assert_eq!(sardy.won, Some(true));
+
+        let wolf = &finished.players[1];
+        assert_eq!(wolf.team, "axis");
+        assert_eq!(wolf.stats.deaths, 1);
+        assert_eq!(wolf.stats.time_played, 601);
+        assert_eq!(wolf.won, Some(false));
+    }
+
+    #[test]
+    fn test_unfinished_match() {
+        let mut tracker = MatchTracker::default();
+        let (first, _) = GAMES_MP_LOG_FIXTURE.split_at(GAMES_MP_LOG_FIXTURE.find("say;").unwrap());
+        assert!(apply(&mut tracker, first).is_empty());
+
+        // The map is changed again before the first one ends
+        let finished = apply(&mut tracker, GAMES_MP_LOG_FIXTURE);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].allies_kills, 1);
+    }
+
+    #[test]
+    fn test_free_for_all_winner() {
+        let log = "  0:00 InitGame: \\g_gametype\\dm\\mapname\\mp_strike
+  0:03 J;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7
+  0:31 K;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;;Big Bad Wolf;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;;^1Sardy^7;m4_mp;135;MOD_RIFLE_BULLET;torso_upper
+  5:00 W;;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7
+  5:01 ShutdownGame:
+";
+        let finished = apply(&mut MatchTracker::default(), log);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].gametype.to_tag(), "dm");
+        assert_eq!(
+            finished[0].winner.as_deref(),
+            Some("0a1b2c3d4e5f60718293a4b5c6d7e8f9")
+        );
+        assert_eq!(finished[0].allies_kills, 0);
+        assert_eq!(finished[0].players[0].score, 1);
+    }
+}
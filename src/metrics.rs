@@ -0,0 +1,299 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::db::DBPool;
+use crate::fleet::Fleet;
+use crate::rcon::RconError;
+use actix_web::Error;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+// The RCON workers are deep inside every CodServer, so the metrics live in a
+// registry of their own shared by the whole process rather than being
+// threaded through every layer.
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    rcon_commands: IntCounterVec,
+    rcon_errors: IntCounterVec,
+    rcon_duration: HistogramVec,
+    db_pool_max_size: IntGauge,
+    db_pool_size: IntGauge,
+    db_pool_available: IntGauge,
+    db_pool_waiting: IntGauge,
+    server_up: IntGaugeVec,
+    server_players: IntGaugeVec,
+    server_max_clients: IntGaugeVec,
+    server_map: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let metrics = Self {
+            registry: Registry::new_custom(Some("olg".to_string()), None)
+                .expect("valid registry prefix"),
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests served"),
+                &["method", "route", "status"],
+            )
+            .expect("valid metric"),
+            http_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Time spent serving HTTP requests",
+                ),
+                &["method", "route"],
+            )
+            .expect("valid metric"),
+            rcon_commands: IntCounterVec::new(
+                Opts::new("rcon_commands_total", "Commands sent to game servers"),
+                &["server", "command"],
+            )
+            .expect("valid metric"),
+            rcon_errors: IntCounterVec::new(
+                Opts::new("rcon_errors_total", "Commands that failed, by reason"),
+                &["server", "command", "error"],
+            )
+            .expect("valid metric"),
+            rcon_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "rcon_command_duration_seconds",
+                    "Time spent waiting for game servers, queueing included",
+                ),
+                &["server", "command"],
+            )
+            .expect("valid metric"),
+            db_pool_max_size: IntGauge::new("db_pool_max_size", "Maximum size of the DB pool")
+                .expect("valid metric"),
+            db_pool_size: IntGauge::new("db_pool_size", "DB connections currently open")
+                .expect("valid metric"),
+            db_pool_available: IntGauge::new(
+                "db_pool_available",
+                "DB connections open and not in use",
+            )
+            .expect("valid metric"),
+            db_pool_waiting: IntGauge::new("db_pool_waiting", "Tasks waiting for a DB connection")
+                .expect("valid metric"),
+            server_up: IntGaugeVec::new(
+                Opts::new("server_up", "Whether the last health check was answered"),
+                &["server"],
+            )
+            .expect("valid metric"),
+            server_players: IntGaugeVec::new(
+                Opts::new("server_players", "Players online at the last health check"),
+                &["server"],
+            )
+            .expect("valid metric"),
+            server_max_clients: IntGaugeVec::new(
+                Opts::new("server_max_clients", "Player slots of the game server"),
+                &["server"],
+            )
+            .expect("valid metric"),
+            server_map: IntGaugeVec::new(
+                Opts::new("server_map", "Map being played, always 1"),
+                &["server", "map"],
+            )
+            .expect("valid metric"),
+        };
+
+        for collector in [
+            Box::new(metrics.http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.http_duration.clone()),
+            Box::new(metrics.rcon_commands.clone()),
+            Box::new(metrics.rcon_errors.clone()),
+            Box::new(metrics.rcon_duration.clone()),
+            Box::new(metrics.db_pool_max_size.clone()),
+            Box::new(metrics.db_pool_size.clone()),
+            Box::new(metrics.db_pool_available.clone()),
+            Box::new(metrics.db_pool_waiting.clone()),
+            Box::new(metrics.server_up.clone()),
+            Box::new(metrics.server_players.clone()),
+            Box::new(metrics.server_max_clients.clone()),
+            Box::new(metrics.server_map.clone()),
+        ] {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        metrics
+    }
+}
+
+// Requests are labelled by the pattern of the route they matched, never by
+// their path, so that ids in the URL do not create new series.
+pub async fn track_http(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let response = next.call(req).await?;
+    let route = response
+        .request()
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    METRICS
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    Ok(response)
+}
+
+pub fn observe_rcon<T>(
+    server: &str,
+    command: &str,
+    duration: Duration,
+    result: &Result<T, RconError>,
+) {
+    METRICS
+        .rcon_commands
+        .with_label_values(&[server, command])
+        .inc();
+    METRICS
+        .rcon_duration
+        .with_label_values(&[server, command])
+        .observe(duration.as_secs_f64());
+    if let Err(error) = result {
+        let reason = match error {
+            RconError::Timeout => "timeout",
+            RconError::Unreachable(_) => "unreachable",
+            RconError::BadPassword => "bad_password",
+            RconError::MalformedReply(_) => "malformed_reply",
+            RconError::UnexpectedReply(_) => "unexpected_reply",
+            RconError::ResponseTooLarge(_) => "response_too_large",
+            RconError::QueueClosed => "queue_closed",
+            RconError::InvalidArgument(_) => "invalid_argument",
+            RconError::MapNotFound(_) => "map_not_found",
+            RconError::UnknownCommand(_) => "unknown_command",
+        };
+        METRICS
+            .rcon_errors
+            .with_label_values(&[server, command, reason])
+            .inc();
+    }
+}
+
+// Gauges are refreshed from the pool and from the last health checks right
+// before every scrape.
+pub fn render(db_pool: &DBPool, fleet: &Fleet) -> anyhow::Result<String> {
+    let status = db_pool.status();
+    METRICS
+        .db_pool_max_size
+        .set(i64::try_from(status.max_size)?);
+    METRICS.db_pool_size.set(i64::try_from(status.size)?);
+    METRICS
+        .db_pool_available
+        .set(i64::try_from(status.available)?);
+    METRICS.db_pool_waiting.set(i64::try_from(status.waiting)?);
+
+    // Dropping every map first removes the series of the previous ones
+    METRICS.server_map.reset();
+    for definition in fleet.definitions() {
+        let Some(sample) = fleet.health().get(&definition.id) else {
+            continue;
+        };
+        let server = definition.id.as_str();
+        METRICS
+            .server_up
+            .with_label_values(&[server])
+            .set(i64::from(sample.online));
+        METRICS
+            .server_players
+            .with_label_values(&[server])
+            .set(i64::from(sample.players.unwrap_or(0)));
+        if let Some(max_players) = sample.max_players {
+            METRICS
+                .server_max_clients
+                .with_label_values(&[server])
+                .set(i64::from(max_players));
+        }
+        if let Some(map) = &sample.map {
+            METRICS.server_map.with_label_values(&[server, map]).set(1);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&METRICS.registry.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::HealthSample;
+
+    #[tokio::test]
+    async fn test_render() -> anyhow::Result<()> {
+        let db_pool = crate::db::create_pool("127.0.0.1", 5432, None, None, "olg")?;
+        let fleet = Fleet::new(
+            vec![crate::fleet::tests::definition("metrics", 28960)],
+            &Default::default(),
+        )?;
+        fleet.health().update(
+            "metrics",
+            HealthSample {
+                time: chrono::Utc::now(),
+                online: true,
+                latency: Some(12),
+                players: Some(3),
+                max_players: Some(18),
+                map: Some("mp_crash".to_string()),
+                gametype: Some("war".to_string()),
+            },
+        );
+        observe_rcon::<()>(
+            "metrics",
+            "status",
+            Duration::from_millis(20),
+            &Err(RconError::Timeout),
+        );
+
+        // Console commands are free text and never become a label
+        let mock_server = crate::server::tests::MockCodServer::new().await;
+        crate::server::CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .console("olg_free_text argument")
+            .await?;
+
+        let text = render(&db_pool, &fleet)?;
+        assert!(text.contains("command=\"console\""));
+        assert!(!text.contains("olg_free_text"));
+        assert!(text.contains("olg_rcon_commands_total{command=\"status\",server=\"metrics\"} 1"));
+        assert!(text.contains(
+            "olg_rcon_errors_total{command=\"status\",error=\"timeout\",server=\"metrics\"} 1"
+        ));
+        assert!(text.contains("olg_server_players{server=\"metrics\"} 3"));
+        assert!(text.contains("olg_server_map{map=\"mp_crash\",server=\"metrics\"} 1"));
+        assert!(text.contains("olg_db_pool_size 0"));
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use chrono::{DateTime, Utc};
+use regex::{RegexSet, RegexSetBuilder};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct InvalidCommand(String);
+
+impl std::fmt::Display for InvalidCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Command not allowed: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidCommand {}
+
+// Length of console_log.command
+pub const MAX_CONSOLE_COMMAND_LENGTH: usize = 1024;
+
+const DEFAULT_DENY: &[&str] = &[
+    r"\brcon_password\b",
+    r"\bsv_privatepassword\b",
+    r"\bg_password\b",
+    r"^quit\b",
+    r"^killserver\b",
+    r"^exec\b",
+    r"^vstr\b",
+    r"^writeconfig\b",
+    r"^cvarlist\b",
+    r"^dvarlist\b",
+    r"^dvardump\b",
+];
+
+// Commands writing the dvar named by their first argument
+const SET_COMMANDS: &[&str] = &[
+    "set",
+    "seta",
+    "sets",
+    "setu",
+    "reset",
+    "toggle",
+    "setfromdvar",
+];
+
+#[derive(Deserialize)]
+struct ConsolePatterns {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default = "default_deny")]
+    deny: Vec<String>,
+}
+
+fn default_deny() -> Vec<String> {
+    DEFAULT_DENY.iter().map(ToString::to_string).collect()
+}
+
+// Commands are matched case-insensitively, like the engine parses them, on
+// the words it reads out of the line. A deny pattern always wins; without
+// allow patterns everything else passes.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "ConsolePatterns")]
+pub struct ConsolePolicy {
+    allow: RegexSet,
+    deny: RegexSet,
+}
+
+impl TryFrom<ConsolePatterns> for ConsolePolicy {
+    type Error = regex::Error;
+
+    fn try_from(patterns: ConsolePatterns) -> Result<Self, Self::Error> {
+        let build = |patterns: &[String]| {
+            RegexSetBuilder::new(patterns)
+                .case_insensitive(true)
+                .build()
+        };
+        Ok(Self {
+            allow: build(&patterns.allow)?,
+            deny: build(&patterns.deny)?,
+        })
+    }
+}
+
+impl Default for ConsolePolicy {
+    fn default() -> Self {
+        ConsolePatterns {
+            allow: Vec::new(),
+            deny: default_deny(),
+        }
+        .try_into()
+        .expect("default patterns are valid")
+    }
+}
+
+impl ConsolePolicy {
+    // The engine runs every piece of a line split on semicolons and line
+    // breaks, which would let a denied command ride along an allowed one.
+    pub fn check(&self, command: &str) -> Result<(), InvalidCommand> {
+        if command.contains(';') || command.chars().any(char::is_control) {
+            return Err(InvalidCommand("one command at a time".to_string()));
+        }
+        let tokens = tokenize(command);
+        let Some(name) = tokens.first().filter(|name| !name.is_empty()) else {
+            return Err(InvalidCommand("empty command".to_string()));
+        };
+        let line = tokens.join(" ");
+        // Writing a denied dvar is denied like the dvar itself
+        let denied_write = SET_COMMANDS.contains(&name.to_lowercase().as_str())
+            && tokens.get(1).is_some_and(|dvar| self.deny.is_match(dvar));
+        if denied_write || self.deny.is_match(&line) {
+            return Err(InvalidCommand(command.to_string()));
+        }
+        if !self.allow.is_empty() && !self.allow.is_match(&line) {
+            return Err(InvalidCommand(command.to_string()));
+        }
+        Ok(())
+    }
+}
+
+// Only the name of a command, for places where its arguments, passwords
+// among them, must not show up
+pub fn command_name(command: &str) -> String {
+    tokenize(command).into_iter().next().unwrap_or_default()
+}
+
+// Splits a line the way the engine does before running it: words are
+// separated by whitespace, quotes group them and are dropped, and comments
+// are skipped. Matching the raw text instead would let "quit" through.
+fn tokenize(command: &str) -> Vec<String> {
+    let chars = command.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i..] {
+            [c, ..] if c.is_whitespace() => i += 1,
+            ['/', '/', ..] => break,
+            ['/', '*', ..] => {
+                i += 2;
+                while i < chars.len() && !matches!(chars[i..], ['*', '/', ..]) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            ['"', ..] => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|c| *c == '"')
+                    .map_or(chars.len(), |end| i + 1 + end);
+                tokens.push(chars[i + 1..end].iter().collect());
+                i = end + 1;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && chars[i] != '"'
+                    && !matches!(chars[i..], ['/', '/' | '*', ..])
+                {
+                    i += 1;
+                }
+                tokens.push(chars[start..i].iter().collect());
+            }
+        }
+    }
+    tokens
+}
+
+// One use of the console, whether it was let through or not. The reply is
+// missing while the command is running and when it failed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleEntry {
+    pub id: Uuid,
+    pub server_id: String,
+    pub command: String,
+    pub allowed: bool,
+    pub reply: Option<String>,
+    pub error: Option<String>,
+    pub remote_addr: Option<String>,
+    pub requested_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = ConsolePolicy::default();
+        assert!(policy.check("status").is_ok());
+        assert!(policy.check("g_gametype sd").is_ok());
+        assert!(policy.check("rcon_password secret").is_err());
+        assert!(policy.check("seta RCON_PASSWORD secret").is_err());
+        assert!(policy.check("  Quit").is_err());
+        assert!(policy.check("exec server.cfg").is_err());
+        assert!(policy.check("execute").is_ok());
+        assert!(policy.check("dvardump").is_err());
+        assert!(policy.check("cvarlist sv_").is_err());
+        assert!(policy.check("status; quit").is_err());
+        assert!(policy.check("status\nquit").is_err());
+        assert!(policy.check("   ").is_err());
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("  status "), vec!["status"]);
+        assert_eq!(tokenize("\"quit\""), vec!["quit"]);
+        assert_eq!(
+            tokenize("say \"hello there\" all"),
+            vec!["say", "hello there", "all"]
+        );
+        assert_eq!(tokenize("qu\"it\""), vec!["qu", "it"]);
+        assert_eq!(tokenize("map mp_crash // comment"), vec!["map", "mp_crash"]);
+        assert_eq!(tokenize("/* comment */status"), vec!["status"]);
+        assert_eq!(tokenize("say \"unterminated"), vec!["say", "unterminated"]);
+        assert!(tokenize("// only a comment").is_empty());
+    }
+
+    #[test]
+    fn test_bypasses() {
+        let policy = ConsolePolicy::default();
+        assert!(policy.check("\"quit\"").is_err());
+        assert!(policy.check("\"exec\" server.cfg").is_err());
+        assert!(policy.check("/**/quit").is_err());
+        assert!(policy.check("set x quit").is_ok());
+        assert!(policy.check("vstr x").is_err());
+        assert!(policy.check("set g_password secret").is_err());
+        assert!(policy.check("seta \"rcon_password\" secret").is_err());
+        assert!(policy.check("sv_privatepassword secret").is_err());
+        assert!(policy.check("\"\"").is_err());
+        assert!(policy.check("// quit").is_err());
+
+        // Writes are checked against the name of the dvar alone as well
+        let policy: ConsolePolicy = serde_json::from_str(r#"{"deny": ["^sv_hostname$"]}"#).unwrap();
+        assert!(policy.check("sv_hostname").is_err());
+        assert!(policy.check("set sv_hostname x").is_err());
+        assert!(policy.check("SETA \"sv_hostname\" x").is_err());
+        assert!(policy.check("set sv_hostname2 x").is_ok());
+    }
+
+    #[test]
+    fn test_configured_policy() {
+        let policy: ConsolePolicy = serde_json::from_str(
+            r#"{"allow": ["^status$", "^(say|tell) "], "deny": ["^tell 0 "]}"#,
+        )
+        .unwrap();
+        assert!(policy.check("status").is_ok());
+        assert!(policy.check("say hello").is_ok());
+        assert!(policy.check("tell 1 hi").is_ok());
+        assert!(policy.check("tell 0 hi").is_err());
+        assert!(policy.check("map_restart").is_err());
+        // Missing deny patterns fall back to the default ones
+        assert!(
+            serde_json::from_str::<ConsolePolicy>(r#"{"allow": ["quit"]}"#)
+                .unwrap()
+                .check("quit")
+                .is_err()
+        );
+        assert!(
+            serde_json::from_str::<ConsolePolicy>(r#"{"deny": []}"#)
+                .unwrap()
+                .check("quit")
+                .is_ok()
+        );
+        assert!(serde_json::from_str::<ConsolePolicy>(r#"{"deny": ["("]}"#).is_err());
+    }
+}
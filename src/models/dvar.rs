@@ -0,0 +1,233 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct InvalidDvar(String);
+
+impl std::fmt::Display for InvalidDvar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid dvar: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDvar {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Dvar {
+    pub name: String,
+    pub value: String,
+    pub default: Option<String>,
+}
+
+impl FromStr for Dvar {
+    type Err = InvalidDvar;
+
+    // "sv_hostname" is:"^1OLG^7" default:"CoD4Host^7"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidDvar(format!("malformed reply: {}", s.trim()));
+
+        let line = s.lines().next().ok_or_else(invalid)?.trim();
+        let (name, rest) = quoted(line).ok_or_else(invalid)?;
+        let (value, rest) = rest
+            .trim_start()
+            .strip_prefix("is:")
+            .and_then(|rest| quoted(rest.trim_start()))
+            .ok_or_else(invalid)?;
+        let default = rest
+            .trim_start()
+            .strip_prefix("default:")
+            .and_then(|rest| quoted(rest.trim_start()))
+            .map(|(default, _)| strip_color_reset(default).to_string());
+
+        Ok(Dvar {
+            name: name.to_string(),
+            value: strip_color_reset(value).to_string(),
+            default,
+        })
+    }
+}
+
+fn quoted(s: &str) -> Option<(&str, &str)> {
+    let body = s.strip_prefix('"')?;
+    let end = body.find('"')?;
+    Some((&body[..end], &body[end + 1..]))
+}
+
+// The engine appends a color reset to every value it prints
+fn strip_color_reset(value: &str) -> &str {
+    value.strip_suffix("^7").unwrap_or(value)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DvarKind {
+    String { max_length: Option<usize> },
+    Integer { min: Option<i64>, max: Option<i64> },
+    Float { min: Option<f64>, max: Option<f64> },
+    Boolean,
+    Enum { values: Vec<String> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DvarPolicy {
+    #[serde(default)]
+    pub writable: bool,
+    #[serde(flatten)]
+    pub kind: DvarKind,
+}
+
+impl DvarPolicy {
+    pub fn validate(&self, value: &str) -> Result<(), InvalidDvar> {
+        let invalid = |reason: &str| InvalidDvar(format!("{}: {}", value, reason));
+
+        if value.chars().any(|c| c == '"' || c.is_control()) {
+            return Err(invalid("quotes and control characters are not allowed"));
+        }
+
+        match &self.kind {
+            DvarKind::String { max_length } => match max_length {
+                Some(max_length) if value.chars().count() > *max_length => {
+                    Err(invalid(&format!("longer than {} characters", max_length)))
+                }
+                _ => Ok(()),
+            },
+            DvarKind::Integer { min, max } => {
+                let number = value
+                    .parse::<i64>()
+                    .map_err(|_| invalid("not an integer"))?;
+                check_range(number, *min, *max).map_err(|reason| invalid(&reason))
+            }
+            DvarKind::Float { min, max } => {
+                let number = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(|| invalid("not a number"))?;
+                check_range(number, *min, *max).map_err(|reason| invalid(&reason))
+            }
+            DvarKind::Boolean => match value {
+                "0" | "1" => Ok(()),
+                _ => Err(invalid("must be 0 or 1")),
+            },
+            DvarKind::Enum { values } => match values.iter().any(|allowed| allowed == value) {
+                true => Ok(()),
+                false => Err(invalid(&format!("must be one of {}", values.join(", ")))),
+            },
+        }
+    }
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    number: T,
+    min: Option<T>,
+    max: Option<T>,
+) -> Result<(), String> {
+    match (min, max) {
+        (Some(min), _) if number < min => Err(format!("lower than {}", min)),
+        (_, Some(max)) if number > max => Err(format!("greater than {}", max)),
+        _ => Ok(()),
+    }
+}
+
+// Dvar names are case-insensitive in the engine, so lookups are too
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(from = "HashMap<String, DvarPolicy>")]
+pub struct DvarAllowlist(HashMap<String, DvarPolicy>);
+
+impl From<HashMap<String, DvarPolicy>> for DvarAllowlist {
+    fn from(policies: HashMap<String, DvarPolicy>) -> Self {
+        DvarAllowlist(
+            policies
+                .into_iter()
+                .map(|(name, policy)| (name.to_lowercase(), policy))
+                .collect(),
+        )
+    }
+}
+
+impl DvarAllowlist {
+    pub fn get(&self, name: &str) -> Option<&DvarPolicy> {
+        self.0.get(&name.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dvar() {
+        let dvar: Dvar = "\"sv_hostname\" is:\"^1OLG ^7Public^7\" default:\"CoD4Host^7\"\n"
+            .parse()
+            .unwrap();
+        assert_eq!(dvar.name, "sv_hostname");
+        assert_eq!(dvar.value, "^1OLG ^7Public");
+        assert_eq!(dvar.default, Some("CoD4Host".to_string()));
+
+        let dvar: Dvar = "\"g_gametype\" is: \"war^7\"".parse().unwrap();
+        assert_eq!(dvar.value, "war");
+        assert_eq!(dvar.default, None);
+
+        assert!("Unknown command \"foo\"".parse::<Dvar>().is_err());
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist: DvarAllowlist = serde_json::from_str(
+            r#"{
+                "sv_hostname": {"writable": true, "type": "string", "max_length": 8},
+                "scr_sd_timelimit": {"writable": true, "type": "float", "min": 0, "max": 10},
+                "sv_maxclients": {"type": "integer", "min": 1, "max": 64},
+                "g_gametype": {"writable": true, "type": "enum", "values": ["war", "sd"]},
+                "g_allowvote": {"writable": true, "type": "boolean"}
+            }"#,
+        )
+        .unwrap();
+
+        let hostname = allowlist.get("SV_HOSTNAME").unwrap();
+        assert!(hostname.writable);
+        assert!(hostname.validate("OLG").is_ok());
+        assert!(hostname.validate("Too long name").is_err());
+        assert!(hostname.validate("a\"b").is_err());
+        assert!(hostname.validate("a\nb").is_err());
+
+        let timelimit = allowlist.get("scr_sd_timelimit").unwrap();
+        assert!(timelimit.validate("2.5").is_ok());
+        assert!(timelimit.validate("11").is_err());
+        assert!(timelimit.validate("nan").is_err());
+
+        let maxclients = allowlist.get("sv_maxclients").unwrap();
+        assert!(!maxclients.writable);
+        assert!(maxclients.validate("18").is_ok());
+        assert!(maxclients.validate("0").is_err());
+        assert!(maxclients.validate("1.5").is_err());
+
+        let gametype = allowlist.get("g_gametype").unwrap();
+        assert!(gametype.validate("sd").is_ok());
+        assert!(gametype.validate("dm").is_err());
+
+        let allowvote = allowlist.get("g_allowvote").unwrap();
+        assert!(allowvote.validate("1").is_ok());
+        assert!(allowvote.validate("yes").is_err());
+
+        assert!(allowlist.get("rcon_password").is_none());
+    }
+}
@@ -0,0 +1,438 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::ServerInfo;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct InvalidEvent(String);
+
+impl std::fmt::Display for InvalidEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid event: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidEvent {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LogPlayer {
+    pub guid: String,
+    pub slot: u8,
+    pub name: String,
+}
+
+// The team is empty in gametypes without teams
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Combatant {
+    pub guid: String,
+    pub slot: u8,
+    pub team: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub victim: Combatant,
+    // None when the damage comes from the world (falling, map triggers...)
+    pub attacker: Option<Combatant>,
+    pub weapon: String,
+    pub damage: u32,
+    pub means_of_death: String,
+    pub hit_location: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Chat {
+    pub guid: String,
+    pub slot: u8,
+    pub name: String,
+    pub message: String,
+}
+
+// Outcome of an action taken through the API. It never shows up in the log,
+// it is published next to the game events so that dashboards can follow it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AdminAction {
+    pub action: String,
+    pub target: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    InitGame(ServerInfo),
+    Join(LogPlayer),
+    Quit(LogPlayer),
+    Kill(Hit),
+    Damage(Hit),
+    Say(Chat),
+    SayTeam(Chat),
+    Win(Combatant),
+    Loss(Combatant),
+    ExitLevel,
+    ShutdownGame,
+    AdminAction(AdminAction),
+}
+
+// One line of games_mp.log. The timestamp is the server uptime, printed as
+// minutes and seconds, so it only orders events of the same server process.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub uptime: u32,
+    pub event: GameEvent,
+}
+
+impl GameEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameEvent::InitGame(_) => "init_game",
+            GameEvent::Join(_) => "join",
+            GameEvent::Quit(_) => "quit",
+            GameEvent::Kill(_) => "kill",
+            GameEvent::Damage(_) => "damage",
+            GameEvent::Say(_) => "say",
+            GameEvent::SayTeam(_) => "say_team",
+            GameEvent::Win(_) => "win",
+            GameEvent::Loss(_) => "loss",
+            GameEvent::ExitLevel => "exit_level",
+            GameEvent::ShutdownGame => "shutdown_game",
+            GameEvent::AdminAction(_) => "admin_action",
+        }
+    }
+}
+
+impl FromStr for LogEntry {
+    type Err = InvalidEvent;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s.trim_start().trim_end_matches(['\n', '\r']);
+        let (timestamp, body) = line
+            .split_once(' ')
+            .ok_or_else(|| InvalidEvent(format!("missing timestamp: {}", s)))?;
+        let uptime = parse_uptime(timestamp)
+            .ok_or_else(|| InvalidEvent(format!("malformed timestamp: {}", s)))?;
+        let event = body.trim_start().parse::<GameEvent>()?;
+        Ok(LogEntry { uptime, event })
+    }
+}
+
+impl FromStr for GameEvent {
+    type Err = InvalidEvent;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidEvent(format!("malformed line: {}", s));
+
+        if let Some(infostring) = s.strip_prefix("InitGame:") {
+            return infostring
+                .trim_start()
+                .parse::<ServerInfo>()
+                .map(GameEvent::InitGame)
+                .map_err(|_| invalid());
+        }
+        if s.starts_with("ExitLevel:") {
+            return Ok(GameEvent::ExitLevel);
+        }
+        if s.starts_with("ShutdownGame:") {
+            return Ok(GameEvent::ShutdownGame);
+        }
+
+        // Names may contain semicolons, and chat messages even more so, so
+        // the last field takes whatever is left of the line.
+        let (kind, rest) = s.split_once(';').ok_or_else(invalid)?;
+        match kind {
+            "J" | "Q" => {
+                let fields = rest.splitn(3, ';').collect::<Vec<&str>>();
+                let [guid, slot, name] = fields[..] else {
+                    return Err(invalid());
+                };
+                let player = LogPlayer {
+                    guid: guid.to_string(),
+                    slot: slot.parse().map_err(|_| invalid())?,
+                    name: name.to_string(),
+                };
+                Ok(match kind {
+                    "J" => GameEvent::Join(player),
+                    _ => GameEvent::Quit(player),
+                })
+            }
+            "K" | "D" => {
+                let fields = rest.split(';').collect::<Vec<&str>>();
+                let [
+                    victim_guid,
+                    victim_slot,
+                    victim_team,
+                    victim_name,
+                    attacker_guid,
+                    attacker_slot,
+                    attacker_team,
+                    attacker_name,
+                    weapon,
+                    damage,
+                    means_of_death,
+                    hit_location,
+                ] = fields[..]
+                else {
+                    return Err(invalid());
+                };
+                let attacker = match attacker_slot {
+                    "-1" => None,
+                    slot => Some(Combatant {
+                        guid: attacker_guid.to_string(),
+                        slot: slot.parse().map_err(|_| invalid())?,
+                        team: attacker_team.to_string(),
+                        name: attacker_name.to_string(),
+                    }),
+                };
+                let hit = Hit {
+                    victim: Combatant {
+                        guid: victim_guid.to_string(),
+                        slot: victim_slot.parse().map_err(|_| invalid())?,
+                        team: victim_team.to_string(),
+                        name: victim_name.to_string(),
+                    },
+                    attacker,
+                    weapon: weapon.to_string(),
+                    damage: damage.parse().map_err(|_| invalid())?,
+                    means_of_death: means_of_death.to_string(),
+                    hit_location: hit_location.to_string(),
+                };
+                Ok(match kind {
+                    "K" => GameEvent::Kill(hit),
+                    _ => GameEvent::Damage(hit),
+                })
+            }
+            "say" | "sayteam" => {
+                let fields = rest.splitn(4, ';').collect::<Vec<&str>>();
+                let [guid, slot, name, message] = fields[..] else {
+                    return Err(invalid());
+                };
+                // The engine prefixes chat with a NAK control character
+                let chat = Chat {
+                    guid: guid.to_string(),
+                    slot: slot.parse().map_err(|_| invalid())?,
+                    name: name.to_string(),
+                    message: message.trim_start_matches('\u{15}').to_string(),
+                };
+                Ok(match kind {
+                    "say" => GameEvent::Say(chat),
+                    _ => GameEvent::SayTeam(chat),
+                })
+            }
+            "W" | "L" => {
+                let fields = rest.splitn(4, ';').collect::<Vec<&str>>();
+                let [team, guid, slot, name] = fields[..] else {
+                    return Err(invalid());
+                };
+                let combatant = Combatant {
+                    guid: guid.to_string(),
+                    slot: slot.parse().map_err(|_| invalid())?,
+                    team: team.to_string(),
+                    name: name.to_string(),
+                };
+                Ok(match kind {
+                    "W" => GameEvent::Win(combatant),
+                    _ => GameEvent::Loss(combatant),
+                })
+            }
+            _ => Err(InvalidEvent(format!("unsupported line: {}", s))),
+        }
+    }
+}
+
+fn parse_uptime(timestamp: &str) -> Option<u32> {
+    let (minutes, seconds) = timestamp.split_once(':')?;
+    let minutes = minutes.parse::<u32>().ok()?;
+    let seconds = seconds
+        .parse::<u32>()
+        .ok()
+        .filter(|seconds| *seconds < 60)?;
+    minutes.checked_mul(60)?.checked_add(seconds)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const GAMES_MP_LOG_FIXTURE: &str = "  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\g_compassShowEnemies\\0\\g_gametype\\war\\gamename\\Call of Duty 4\\mapname\\mp_crash\\protocol\\6\\shortversion\\1.7\\sv_hostname\\^1OLG ^7Public TDM\\sv_maxclients\\18\\sv_privateClients\\2
+  0:03 J;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7
+  0:04 J;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;Big Bad Wolf
+  0:12 Weapon;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7;m4_mp
+  0:31 D;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;axis;Big Bad Wolf;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;allies;^1Sardy^7;m4_mp;30;MOD_RIFLE_BULLET;torso_upper
+  0:31 K;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;axis;Big Bad Wolf;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;allies;^1Sardy^7;m4_mp;135;MOD_HEAD_SHOT;head
+  0:58 K;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;allies;^1Sardy^7;;-1;world;;none;100000;MOD_FALLING;none
+  1:02 say;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;Big Bad Wolf;\u{15}nice one; really
+  1:05 sayteam;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7;\u{15}push B
+ 10:00 W;allies;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7
+ 10:00 L;axis;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;Big Bad Wolf
+ 10:00 ExitLevel: executed
+ 10:05 Q;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;Big Bad Wolf
+ 10:05 ShutdownGame:
+ 10:05 ------------------------------------------------------------
+";
+
+    fn entries() -> Vec<LogEntry> {
+        GAMES_MP_LOG_FIXTURE
+            .lines()
+            .filter_map(|line| line.parse::<LogEntry>().ok())
+            .collect()
+    }
+
+    fn sardy(team: &str) -> Combatant {
+        Combatant {
+            guid: "0a1b2c3d4e5f60718293a4b5c6d7e8f9".to_string(),
+            slot: 0,
+            team: team.to_string(),
+            name: "^1Sardy^7".to_string(),
+        }
+    }
+
+    fn wolf(team: &str) -> Combatant {
+        Combatant {
+            guid: "f9e8d7c6b5a4938271605f4e3d2c1b0a".to_string(),
+            slot: 1,
+            team: team.to_string(),
+            name: "Big Bad Wolf".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_fixture() {
+        let kinds = entries()
+            .iter()
+            .map(|entry| entry.event.kind())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            kinds,
+            vec![
+                "init_game",
+                "join",
+                "join",
+                "damage",
+                "kill",
+                "kill",
+                "say",
+                "say_team",
+                "win",
+                "loss",
+                "exit_level",
+                "quit",
+                "shutdown_game",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_init_game() {
+        let entry = &entries()[0];
+        assert_eq!(entry.uptime, 0);
+        let GameEvent::InitGame(info) = &entry.event else {
+            panic!("not an InitGame: {:?}", entry.event);
+        };
+        assert_eq!(info.map, "mp_crash");
+        assert_eq!(info.gametype, "war");
+        assert_eq!(info.max_clients, Some(18));
+    }
+
+    #[test]
+    fn test_parse_join_quit() {
+        let entries = entries();
+        assert_eq!(
+            entries[1].event,
+            GameEvent::Join(LogPlayer {
+                guid: "0a1b2c3d4e5f60718293a4b5c6d7e8f9".to_string(),
+                slot: 0,
+                name: "^1Sardy^7".to_string(),
+            })
+        );
+        assert_eq!(entries[11].uptime, 605);
+        assert!(matches!(&entries[11].event, GameEvent::Quit(player) if player.slot == 1));
+    }
+
+    #[test]
+    fn test_parse_kill_damage() {
+        let entries = entries();
+        assert_eq!(entries[4].uptime, 31);
+        assert_eq!(
+            entries[4].event,
+            GameEvent::Kill(Hit {
+                victim: wolf("axis"),
+                attacker: Some(sardy("allies")),
+                weapon: "m4_mp".to_string(),
+                damage: 135,
+                means_of_death: "MOD_HEAD_SHOT".to_string(),
+                hit_location: "head".to_string(),
+            })
+        );
+        assert!(matches!(&entries[3].event, GameEvent::Damage(hit) if hit.damage == 30));
+        let GameEvent::Kill(hit) = &entries[5].event else {
+            panic!("not a kill: {:?}", entries[5].event);
+        };
+        assert_eq!(hit.victim, sardy("allies"));
+        assert_eq!(hit.attacker, None);
+        assert_eq!(hit.means_of_death, "MOD_FALLING");
+    }
+
+    #[test]
+    fn test_parse_chat() {
+        let entries = entries();
+        assert_eq!(
+            entries[6].event,
+            GameEvent::Say(Chat {
+                guid: "f9e8d7c6b5a4938271605f4e3d2c1b0a".to_string(),
+                slot: 1,
+                name: "Big Bad Wolf".to_string(),
+                message: "nice one; really".to_string(),
+            })
+        );
+        assert!(matches!(&entries[7].event, GameEvent::SayTeam(chat) if chat.message == "push B"));
+    }
+
+    #[test]
+    fn test_parse_win_loss() {
+        let entries = entries();
+        assert_eq!(entries[8].uptime, 600);
+        assert_eq!(entries[8].event, GameEvent::Win(sardy("allies")));
+        assert_eq!(entries[9].event, GameEvent::Loss(wolf("axis")));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("".parse::<LogEntry>().is_err());
+        assert!("InitGame: \\mapname\\mp_crash".parse::<LogEntry>().is_err());
+        assert!(" 0:61 ShutdownGame:".parse::<LogEntry>().is_err());
+        assert!(" 0:12 J;abc;zero;Sardy".parse::<LogEntry>().is_err());
+        assert!(" 0:12 K;abc;0;axis;Sardy".parse::<LogEntry>().is_err());
+        assert!(
+            " 0:12 Weapon;abc;0;Sardy;m4_mp"
+                .parse::<LogEntry>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_serialize_event() {
+        let json = serde_json::to_value(&entries()[1].event).unwrap();
+        assert_eq!(json["type"], "join");
+        assert_eq!(json["slot"], 0);
+        let json = serde_json::to_value(GameEvent::ShutdownGame).unwrap();
+        assert_eq!(json["type"], "shutdown_game");
+    }
+}
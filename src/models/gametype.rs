@@ -19,6 +19,8 @@ use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::str::FromStr;
 
+const MAX_TAG_LENGTH: usize = 32;
+
 #[derive(Debug)]
 pub struct InvalidGametype(String);
 
@@ -30,28 +32,27 @@ impl std::fmt::Display for InvalidGametype {
 
 impl std::error::Error for InvalidGametype {}
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+// The short tag g_gametype is set to. Which tags exist is up to the catalog
+// in the database, mods add their own, so only their shape is checked here.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
-pub enum Gametype {
-    FreeForAll,
-    TeamDeathmatch,
-    Domination,
-    SearchAndDestroy,
-    Headquarters,
-    Sabotage,
-}
+pub struct Gametype(String);
 
 impl FromStr for Gametype {
     type Err = InvalidGametype;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Gametype::from_tag(s).ok_or_else(|| InvalidGametype(s.to_string()))
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        if s.is_empty() || s.len() > MAX_TAG_LENGTH || !s.chars().all(valid) {
+            return Err(InvalidGametype(s.to_string()));
+        }
+        Ok(Gametype(s.to_ascii_lowercase()))
     }
 }
 
 impl From<Gametype> for String {
     fn from(gt: Gametype) -> Self {
-        gt.to_tag().to_string()
+        gt.0
     }
 }
 
@@ -59,79 +60,68 @@ impl TryFrom<String> for Gametype {
     type Error = InvalidGametype;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        Gametype::from_tag(&s).ok_or_else(|| InvalidGametype(s))
+        s.parse()
     }
 }
 
 impl Gametype {
     pub fn to_tag(&self) -> &str {
-        match self {
-            Gametype::FreeForAll => "dm",
-            Gametype::TeamDeathmatch => "war",
-            Gametype::Domination => "dom",
-            Gametype::SearchAndDestroy => "sd",
-            Gametype::Headquarters => "koth",
-            Gametype::Sabotage => "sab",
-        }
+        &self.0
     }
+}
 
-    pub fn from_tag(tag: &str) -> Option<Self> {
-        match tag {
-            "dm" => Some(Gametype::FreeForAll),
-            "war" => Some(Gametype::TeamDeathmatch),
-            "dom" => Some(Gametype::Domination),
-            "sd" => Some(Gametype::SearchAndDestroy),
-            "koth" => Some(Gametype::Headquarters),
-            "sab" => Some(Gametype::Sabotage),
-            _ => None,
-        }
-    }
+// Entry of the gametype catalog. Stock gametypes have no source mod.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GametypeDefinition {
+    pub tag: Gametype,
+    pub name: String,
+    pub description: Option<String>,
+    pub team_based: bool,
+    pub source_mod: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json;
-
-    #[test]
-    fn test_gametype_to_tag() {
-        assert_eq!(Gametype::FreeForAll.to_tag(), "dm");
-        assert_eq!(Gametype::TeamDeathmatch.to_tag(), "war");
-        assert_eq!(Gametype::Domination.to_tag(), "dom");
-        assert_eq!(Gametype::SearchAndDestroy.to_tag(), "sd");
-        assert_eq!(Gametype::Headquarters.to_tag(), "koth");
-        assert_eq!(Gametype::Sabotage.to_tag(), "sab");
-    }
 
     #[test]
     fn test_gametype_from_tag() {
-        assert_eq!(Gametype::from_tag("dm"), Some(Gametype::FreeForAll));
-        assert_eq!(Gametype::from_tag("war"), Some(Gametype::TeamDeathmatch));
-        assert_eq!(Gametype::from_tag("dom"), Some(Gametype::Domination));
-        assert_eq!(Gametype::from_tag("sd"), Some(Gametype::SearchAndDestroy));
-        assert_eq!(Gametype::from_tag("koth"), Some(Gametype::Headquarters));
-        assert_eq!(Gametype::from_tag("sab"), Some(Gametype::Sabotage));
-        assert_eq!(Gametype::from_tag("invalid"), None);
+        for tag in [
+            "dm", "war", "dom", "sd", "koth", "sab", "gungame", "hns", "ctf",
+        ] {
+            assert_eq!(tag.parse::<Gametype>().unwrap().to_tag(), tag);
+        }
+        assert_eq!("WAR".parse::<Gametype>().unwrap().to_tag(), "war");
+        assert!("".parse::<Gametype>().is_err());
+        assert!("war; quit".parse::<Gametype>().is_err());
+        assert!("search and destroy".parse::<Gametype>().is_err());
+        assert!("a".repeat(MAX_TAG_LENGTH + 1).parse::<Gametype>().is_err());
     }
 
     #[test]
     fn test_serde_serialization_deserialization() {
-        let gametype = Gametype::FreeForAll;
+        let gametype = "dm".parse::<Gametype>().unwrap();
         let serialized = serde_json::to_string(&gametype).unwrap();
         assert_eq!(serialized, "\"dm\"");
 
         let deserialized: Gametype = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, gametype);
 
-        let gametype = Gametype::TeamDeathmatch;
-        let serialized = serde_json::to_string(&gametype).unwrap();
-        assert_eq!(serialized, "\"war\"");
-
-        let deserialized: Gametype = serde_json::from_str(&serialized).unwrap();
-        assert_eq!(deserialized, gametype);
+        let deserialized: Gametype = serde_json::from_str("\"gungame\"").unwrap();
+        assert_eq!(deserialized.to_tag(), "gungame");
 
-        let invalid_json = "\"invalid\"";
+        let invalid_json = "\"in valid\"";
         let deserialized_result: Result<Gametype, _> = serde_json::from_str(invalid_json);
         assert!(deserialized_result.is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_definition() {
+        let definition: GametypeDefinition = serde_json::from_str(
+            r#"{"tag": "gungame", "name": "Gun Game", "description": null, "team_based": false, "source_mod": "gungame"}"#,
+        )
+        .unwrap();
+        assert_eq!(definition.tag.to_tag(), "gungame");
+        assert!(!definition.team_based);
+    }
+}
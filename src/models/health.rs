@@ -0,0 +1,76 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::TimeWindow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Outcome of one check of a game server. Everything but the time is missing
+// when the server did not answer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HealthSample {
+    pub time: DateTime<Utc>,
+    pub online: bool,
+    // Round trip of the getinfo query, in milliseconds
+    pub latency: Option<i32>,
+    pub players: Option<i32>,
+    pub max_players: Option<i32>,
+    pub map: Option<String>,
+    pub gametype: Option<String>,
+}
+
+// The status is missing until the server has been checked for the first
+// time, the uptime when there are no checks in the window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerHealth {
+    pub server_id: String,
+    pub window: TimeWindow,
+    pub status: Option<HealthSample>,
+    pub checks: i64,
+    pub uptime: Option<f64>,
+}
+
+impl ServerHealth {
+    pub fn new(
+        server_id: &str,
+        window: TimeWindow,
+        status: Option<HealthSample>,
+        checks: i64,
+        online: i64,
+    ) -> Self {
+        Self {
+            server_id: server_id.to_string(),
+            window,
+            status,
+            checks,
+            uptime: (checks > 0).then(|| online as f64 * 100.0 / checks as f64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uptime_percentage() {
+        let health = ServerHealth::new("tdm", TimeWindow::Week, None, 8, 6);
+        assert_eq!(health.uptime, Some(75.0));
+        let health = ServerHealth::new("tdm", TimeWindow::Today, None, 0, 0);
+        assert_eq!(health.uptime, None);
+    }
+}
@@ -0,0 +1,185 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct InvalidInfo(String);
+
+impl std::fmt::Display for InvalidInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid info: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidInfo {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub hostname: String,
+    pub map: String,
+    pub gametype: String,
+    pub clients: Option<u8>,
+    pub max_clients: Option<u8>,
+    pub dvars: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryPlayer {
+    pub score: i32,
+    pub ping: u16,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryStatus {
+    pub info: ServerInfo,
+    pub players: Vec<QueryPlayer>,
+}
+
+impl FromStr for ServerInfo {
+    type Err = InvalidInfo;
+
+    // getinfo and getstatus use different keys for the same values, so both
+    // spellings are accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dvars = parse_infostring(s)?;
+        let lookup = |keys: &[&str]| keys.iter().find_map(|key| dvars.get(*key).cloned());
+
+        Ok(ServerInfo {
+            hostname: lookup(&["hostname", "sv_hostname"]).unwrap_or_default(),
+            map: lookup(&["mapname"]).ok_or_else(|| InvalidInfo("missing mapname".to_string()))?,
+            gametype: lookup(&["gametype", "g_gametype"]).unwrap_or_default(),
+            clients: lookup(&["clients"]).and_then(|value| value.parse().ok()),
+            max_clients: lookup(&["sv_maxclients"]).and_then(|value| value.parse().ok()),
+            dvars,
+        })
+    }
+}
+
+impl FromStr for QueryPlayer {
+    type Err = InvalidInfo;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidInfo(format!("malformed player line: {}", s));
+
+        let mut parts = s.trim().splitn(3, ' ');
+        let score = parts.next().ok_or_else(invalid)?;
+        let ping = parts.next().ok_or_else(invalid)?;
+        let name = parts
+            .next()
+            .and_then(|name| name.strip_prefix('"'))
+            .and_then(|name| name.strip_suffix('"'))
+            .ok_or_else(invalid)?;
+
+        Ok(QueryPlayer {
+            score: score.parse().map_err(|_| invalid())?,
+            ping: ping.parse().map_err(|_| invalid())?,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl FromStr for QueryStatus {
+    type Err = InvalidInfo;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().filter(|line| !line.trim().is_empty());
+
+        let mut info = lines
+            .next()
+            .ok_or_else(|| InvalidInfo("missing infostring".to_string()))?
+            .parse::<ServerInfo>()?;
+
+        let players = lines
+            .map(QueryPlayer::from_str)
+            .collect::<Result<Vec<QueryPlayer>, InvalidInfo>>()?;
+
+        info.clients = info.clients.or(u8::try_from(players.len()).ok());
+
+        Ok(QueryStatus { info, players })
+    }
+}
+
+fn parse_infostring(s: &str) -> Result<BTreeMap<String, String>, InvalidInfo> {
+    let s = s.trim_end_matches(['\n', '\r']);
+    let Some(body) = s.strip_prefix('\\') else {
+        return Err(InvalidInfo(format!("not an infostring: {}", s)));
+    };
+
+    let fields = body.split('\\').collect::<Vec<&str>>();
+    if fields.len() % 2 != 0 {
+        return Err(InvalidInfo(format!("unbalanced infostring: {}", s)));
+    }
+
+    Ok(fields
+        .chunks(2)
+        .map(|pair| (pair[0].to_string(), pair[1].to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const GETINFO_FIXTURE: &str = "\\challenge\\olg\\protocol\\6\\hostname\\^1OLG ^7Public TDM\\mapname\\mp_crash\\clients\\3\\sv_maxclients\\18\\gametype\\war\\pure\\1\\kc\\1\\hw\\2\\od\\0\\hc\\0\\pswrd\\0\\ff\\0";
+
+    pub(crate) const GETSTATUS_FIXTURE: &str = "\\fs_game\\\\g_gametype\\war\\mapname\\mp_crash\\shortversion\\1.7\\sv_hostname\\^1OLG ^7Public TDM\\sv_maxclients\\18\\sv_privateClients\\2\\g_compassShowEnemies\\0
+25 48 \"^1Sardy^7\"
+-2 112 \"Big Bad Wolf\"
+0 999 \"Newcomer\"
+";
+
+    #[test]
+    fn test_parse_info() {
+        let info: ServerInfo = GETINFO_FIXTURE.parse().unwrap();
+        assert_eq!(info.hostname, "^1OLG ^7Public TDM");
+        assert_eq!(info.map, "mp_crash");
+        assert_eq!(info.gametype, "war");
+        assert_eq!(info.clients, Some(3));
+        assert_eq!(info.max_clients, Some(18));
+        assert_eq!(info.dvars.get("protocol"), Some(&"6".to_string()));
+    }
+
+    #[test]
+    fn test_parse_query_status() {
+        let status: QueryStatus = GETSTATUS_FIXTURE.parse().unwrap();
+        assert_eq!(status.info.hostname, "^1OLG ^7Public TDM");
+        assert_eq!(status.info.gametype, "war");
+        assert_eq!(status.info.clients, Some(3));
+        assert_eq!(status.info.dvars.get("fs_game"), Some(&String::new()));
+        assert_eq!(status.players.len(), 3);
+        assert_eq!(
+            status.players[1],
+            QueryPlayer {
+                score: -2,
+                ping: 112,
+                name: "Big Bad Wolf".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_info_invalid() {
+        assert!("mapname\\mp_crash".parse::<ServerInfo>().is_err());
+        assert!("\\mapname".parse::<ServerInfo>().is_err());
+        assert!("\\hostname\\foo".parse::<ServerInfo>().is_err());
+        assert!("25 48 Sardy".parse::<QueryPlayer>().is_err());
+    }
+}
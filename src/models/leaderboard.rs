@@ -0,0 +1,133 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::Gametype;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    Kills,
+    KillDeathRatio,
+    HeadshotPercentage,
+    ScorePerMinute,
+    Wins,
+}
+
+// Rolling windows, except for today that starts at midnight UTC
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeWindow {
+    Today,
+    Week,
+    Month,
+    #[default]
+    All,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub guid: String,
+    pub name: String,
+    pub games: i64,
+    pub wins: i64,
+    pub kills: i64,
+    pub deaths: i64,
+    pub headshots: i64,
+    pub score: i64,
+    pub time_played: i64,
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Leaderboard {
+    pub metric: LeaderboardMetric,
+    pub window: TimeWindow,
+    pub gametype: Option<Gametype>,
+    pub map: Option<String>,
+    pub min_games: i64,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl LeaderboardMetric {
+    // Expression ranking the rows grouped by player. Ratios never divide by
+    // zero: a player without deaths is ranked by kills alone.
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            LeaderboardMetric::Kills => "sum(p.kills)::double precision",
+            LeaderboardMetric::KillDeathRatio => {
+                "sum(p.kills)::double precision / GREATEST(sum(p.deaths), 1)"
+            }
+            LeaderboardMetric::HeadshotPercentage => {
+                "sum(p.headshots)::double precision * 100 / GREATEST(sum(p.kills), 1)"
+            }
+            LeaderboardMetric::ScorePerMinute => {
+                "sum(p.score)::double precision * 60 / GREATEST(sum(p.time_played), 1)"
+            }
+            LeaderboardMetric::Wins => "(count(*) FILTER (WHERE p.won))::double precision",
+        }
+    }
+}
+
+impl TimeWindow {
+    pub fn start(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            TimeWindow::Today => Some(now.date_naive().and_time(Default::default()).and_utc()),
+            TimeWindow::Week => Some(now - Duration::days(7)),
+            TimeWindow::Month => Some(now - Duration::days(30)),
+            TimeWindow::All => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_start() {
+        let now = "2025-03-14T15:09:26Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            TimeWindow::Today.start(now),
+            Some("2025-03-14T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            TimeWindow::Week.start(now),
+            Some("2025-03-07T15:09:26Z".parse().unwrap())
+        );
+        assert_eq!(
+            TimeWindow::Month.start(now),
+            Some("2025-02-12T15:09:26Z".parse().unwrap())
+        );
+        assert_eq!(TimeWindow::All.start(now), None);
+    }
+
+    #[test]
+    fn test_serde_metric() {
+        assert_eq!(
+            serde_json::from_str::<LeaderboardMetric>("\"kill_death_ratio\"").unwrap(),
+            LeaderboardMetric::KillDeathRatio
+        );
+        assert!(serde_json::from_str::<LeaderboardMetric>("\"deaths\"").is_err());
+        assert_eq!(
+            serde_json::to_string(&TimeWindow::Week).unwrap(),
+            "\"week\""
+        );
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::{Gametype, PlayerStats};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// The log has no scoreboard, so scores are counted in frags: kills minus
+// team kills and suicides for players, kills of its members for a team.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MatchPlayer {
+    #[serde(flatten)]
+    pub stats: PlayerStats,
+    pub team: String,
+    pub score: i64,
+    // None when the map ended without a winner (restarts, shutdowns...)
+    pub won: Option<bool>,
+}
+
+// The winner is a team in team gametypes and a player GUID otherwise
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub id: Uuid,
+    pub server_id: String,
+    pub map: String,
+    pub gametype: Gametype,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration: i64,
+    // Kills of the other team, whatever the gametype scores
+    pub allies_kills: i64,
+    pub axis_kills: i64,
+    pub winner: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MatchDetail {
+    #[serde(flatten)]
+    pub summary: Match,
+    pub players: Vec<MatchPlayer>,
+}
+
+impl MatchPlayer {
+    pub fn new(guid: &str, name: &str) -> Self {
+        Self {
+            stats: PlayerStats::new(guid, name),
+            team: String::new(),
+            score: 0,
+            won: None,
+        }
+    }
+
+    pub fn add(&mut self, stats: &PlayerStats) {
+        self.stats.name = stats.name.clone();
+        self.stats.add(stats);
+        self.score = self.stats.kills - self.stats.team_kills - self.stats.suicides;
+    }
+}
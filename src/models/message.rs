@@ -0,0 +1,156 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+// Color codes are a caret followed by a digit (^1 red, ^7 white...)
+fn is_color_code(c: char, next: Option<char>) -> bool {
+    c == '^' && next.is_some_and(|next| next.is_ascii_digit())
+}
+
+pub fn strip_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_color_code(c, chars.peek().copied()) {
+            chars.next();
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
+}
+
+fn last_color(text: &str) -> Option<char> {
+    let chars = text.chars().collect::<Vec<char>>();
+    chars
+        .windows(2)
+        .rev()
+        .find(|pair| is_color_code(pair[0], Some(pair[1])))
+        .map(|pair| pair[1])
+}
+
+// Splits a message into lines the engine will print in full. Words are kept
+// whole when possible, color codes are never cut in half and the color in
+// use at the end of a line is carried over to the next one.
+pub fn split_message(message: &str, max_length: usize) -> Vec<String> {
+    let max_length = max_length.max(4);
+    let cleaned = message
+        .chars()
+        .map(|c| match c {
+            '"' => '\'',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>();
+
+    let is_only_color = |line: &str| line.len() == 2 && line.starts_with('^');
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in cleaned
+        .split_whitespace()
+        .flat_map(|word| split_word(word, max_length - 2))
+    {
+        let separator = if current.is_empty() || is_only_color(&current) {
+            ""
+        } else {
+            " "
+        };
+        if !current.is_empty() && current.len() + separator.len() + word.len() > max_length {
+            let color = last_color(&current);
+            lines.push(std::mem::take(&mut current));
+            if let Some(color) = color {
+                current = format!("^{}", color);
+            }
+        } else {
+            current.push_str(separator);
+        }
+        current.push_str(&word);
+    }
+    if !current.is_empty() && !is_only_color(&current) {
+        lines.push(current);
+    }
+    lines
+}
+
+fn split_word(word: &str, max_length: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let width = if is_color_code(c, next) {
+            2
+        } else {
+            c.len_utf8()
+        };
+        if !piece.is_empty() && piece.len() + width > max_length {
+            pieces.push(std::mem::take(&mut piece));
+        }
+        piece.push(c);
+        if width == 2 && c == '^' {
+            piece.extend(chars.next());
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_colors() {
+        assert_eq!(strip_colors("^1OLG ^7Public^7"), "OLG Public");
+        assert_eq!(strip_colors("100^%"), "100^%");
+        assert_eq!(strip_colors("trailing^"), "trailing^");
+    }
+
+    #[test]
+    fn test_split_short_message() {
+        assert_eq!(
+            split_message("Server restart in ^15^7 minutes", 120),
+            vec!["Server restart in ^15^7 minutes"]
+        );
+        assert!(split_message("   ", 120).is_empty());
+    }
+
+    #[test]
+    fn test_split_long_message() {
+        let lines = split_message("^3one two three four five six", 14);
+        assert_eq!(lines, vec!["^3one two", "^3three four", "^3five six"]);
+        assert!(lines.iter().all(|line| line.len() <= 14));
+    }
+
+    #[test]
+    fn test_split_keeps_color_codes_whole() {
+        let lines = split_message("abcdefgh^2ijklmnop", 10);
+        assert!(lines.iter().all(|line| line.len() <= 10));
+        assert!(lines.iter().all(|line| !line.ends_with('^')));
+        assert_eq!(lines, vec!["abcdefgh", "^2ijklmn", "^2op"]);
+        assert_eq!(strip_colors(&lines.concat()), "abcdefghijklmnop");
+    }
+
+    #[test]
+    fn test_split_sanitizes_message() {
+        assert_eq!(
+            split_message("say \"hi\";\nquit", 120),
+            vec!["say 'hi'; quit"]
+        );
+    }
+}
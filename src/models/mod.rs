@@ -15,6 +15,37 @@
  *
  */
 
+mod console;
+mod dvar;
+mod event;
 mod gametype;
+mod health;
+mod info;
+mod leaderboard;
+mod matches;
+mod message;
+mod page;
+mod rotation;
+mod stats;
+mod status;
 
-pub use gametype::Gametype;
\ No newline at end of file
+pub use console::{ConsoleEntry, ConsolePolicy, MAX_CONSOLE_COMMAND_LENGTH, command_name};
+pub use dvar::{Dvar, DvarAllowlist};
+pub use event::{AdminAction, Combatant, GameEvent, LogEntry};
+pub use gametype::{Gametype, GametypeDefinition};
+pub use health::{HealthSample, ServerHealth};
+pub use info::{QueryStatus, ServerInfo};
+pub use leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardMetric, TimeWindow};
+pub use matches::{Match, MatchDetail, MatchPlayer};
+pub use message::{split_message, strip_colors};
+pub use page::{CursorPage, Page};
+pub use rotation::{MAX_MAP_ROTATION_LENGTH, Rotation, RotationEntry, map_rotation};
+pub use stats::PlayerStats;
+pub use status::{Player, PlayerTarget, PublicPlayer, ServerStatus};
+
+#[cfg(test)]
+pub(crate) use event::tests::GAMES_MP_LOG_FIXTURE;
+#[cfg(test)]
+pub(crate) use info::tests::{GETINFO_FIXTURE, GETSTATUS_FIXTURE};
+#[cfg(test)]
+pub(crate) use status::tests::STATUS_FIXTURE;
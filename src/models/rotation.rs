@@ -0,0 +1,92 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::Gametype;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RotationEntry {
+    pub gametype: Gametype,
+    pub map: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub id: Uuid,
+    pub name: String,
+    pub entries: Vec<RotationEntry>,
+}
+
+// Engine strings hold 1024 characters, the command setting sv_mapRotation
+// included
+pub const MAX_MAP_ROTATION_LENGTH: usize = 1000;
+
+impl Rotation {
+    pub fn to_map_rotation(&self) -> String {
+        map_rotation(&self.entries)
+    }
+}
+
+// Renders the entries as the engine expects them in sv_mapRotation
+pub fn map_rotation(entries: &[RotationEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| format!("gametype {} map {}", entry.gametype.to_tag(), entry.map))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_map_rotation() {
+        let rotation = Rotation {
+            id: Uuid::nil(),
+            name: "public".to_string(),
+            entries: vec![
+                RotationEntry {
+                    gametype: "war".parse().unwrap(),
+                    map: "mp_crash".to_string(),
+                },
+                RotationEntry {
+                    gametype: "sd".parse().unwrap(),
+                    map: "mp_backlot".to_string(),
+                },
+            ],
+        };
+        assert_eq!(
+            rotation.to_map_rotation(),
+            "gametype war map mp_crash gametype sd map mp_backlot"
+        );
+    }
+
+    #[test]
+    fn test_map_rotation_length() {
+        let entries = vec![
+            RotationEntry {
+                gametype: "war".parse().unwrap(),
+                map: "mp_crash".to_string(),
+            };
+            50
+        ];
+        assert!(map_rotation(&entries[..10]).len() <= MAX_MAP_ROTATION_LENGTH);
+        assert!(map_rotation(&entries).len() > MAX_MAP_ROTATION_LENGTH);
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+
+// Totals of a player, or the increments one event brings to them. The name
+// is the last one the player has been seen with; time played is in seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlayerStats {
+    pub guid: String,
+    pub name: String,
+    pub kills: i64,
+    pub deaths: i64,
+    pub headshots: i64,
+    pub suicides: i64,
+    pub team_kills: i64,
+    pub damage: i64,
+    pub time_played: i64,
+}
+
+impl PlayerStats {
+    pub fn new(guid: &str, name: &str) -> Self {
+        Self {
+            guid: guid.to_lowercase(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, other: &PlayerStats) {
+        self.kills += other.kills;
+        self.deaths += other.deaths;
+        self.headshots += other.headshots;
+        self.suicides += other.suicides;
+        self.team_kills += other.team_kills;
+        self.damage += other.damage;
+        self.time_played += other.time_played;
+    }
+}
@@ -0,0 +1,290 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::message::strip_colors;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct InvalidStatus(String);
+
+impl std::fmt::Display for InvalidStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid status: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidStatus {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+    pub slot: u8,
+    pub score: i32,
+    // None while the client is still connecting (CNCT) or timing out (ZMBI)
+    pub ping: Option<u16>,
+    pub guid: String,
+    pub name: String,
+    pub last_msg: u32,
+    pub address: String,
+    pub qport: u16,
+    pub rate: u32,
+}
+
+// What the public API shows of a player. The GUID is what bans are keyed on
+// and the address is personal, both stay behind the admin API.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PublicPlayer {
+    pub slot: u8,
+    pub score: i32,
+    pub ping: Option<u16>,
+    pub name: String,
+}
+
+impl From<&Player> for PublicPlayer {
+    fn from(player: &Player) -> Self {
+        PublicPlayer {
+            slot: player.slot,
+            score: player.score,
+            ping: player.ping,
+            name: player.name.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerStatus {
+    pub map: String,
+    pub players: Vec<Player>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayerTarget {
+    Slot(u8),
+    Name(String),
+    Guid(String),
+}
+
+impl std::fmt::Display for PlayerTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerTarget::Slot(slot) => write!(f, "slot {}", slot),
+            PlayerTarget::Name(name) => write!(f, "name {}", name),
+            PlayerTarget::Guid(guid) => write!(f, "GUID {}", guid),
+        }
+    }
+}
+
+impl ServerStatus {
+    // Names are compared without color codes, as players see them. They are
+    // not unique in the engine: a name matching more than one player resolves
+    // to nobody rather than to a random one of them.
+    pub fn find(&self, target: &PlayerTarget) -> Option<&Player> {
+        let mut matches = self.players.iter().filter(|player| match target {
+            PlayerTarget::Slot(slot) => player.slot == *slot,
+            PlayerTarget::Name(name) => strip_colors(&player.name) == strip_colors(name),
+            PlayerTarget::Guid(guid) => player.guid.eq_ignore_ascii_case(guid),
+        });
+        match (matches.next(), matches.next()) {
+            (Some(player), None) => Some(player),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Player {
+    type Err = InvalidStatus;
+
+    // The name may contain spaces, so fixed-width columns are taken from both
+    // ends of the row and whatever is left in the middle is the name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidStatus(format!("malformed player row: {}", s));
+
+        let mut rest = s.trim();
+        let slot = take_left(&mut rest).ok_or_else(invalid)?;
+        let score = take_left(&mut rest).ok_or_else(invalid)?;
+        let ping = take_left(&mut rest).ok_or_else(invalid)?;
+        let guid = take_left(&mut rest).ok_or_else(invalid)?;
+        let rate = take_right(&mut rest).ok_or_else(invalid)?;
+        let qport = take_right(&mut rest).ok_or_else(invalid)?;
+        let address = take_right(&mut rest).ok_or_else(invalid)?;
+        let last_msg = take_right(&mut rest).ok_or_else(invalid)?;
+
+        Ok(Player {
+            slot: slot.parse().map_err(|_| invalid())?,
+            score: score.parse().map_err(|_| invalid())?,
+            ping: match ping {
+                "CNCT" | "ZMBI" => None,
+                value => Some(value.parse().map_err(|_| invalid())?),
+            },
+            guid: guid.to_string(),
+            name: rest.trim().to_string(),
+            last_msg: last_msg.parse().map_err(|_| invalid())?,
+            address: address.to_string(),
+            qport: qport.parse().map_err(|_| invalid())?,
+            rate: rate.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl FromStr for ServerStatus {
+    type Err = InvalidStatus;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim_end).skip_while(|l| l.is_empty());
+
+        let map = lines
+            .next()
+            .and_then(|line| line.strip_prefix("map:"))
+            .map(|map| map.trim().to_string())
+            .ok_or_else(|| InvalidStatus("missing map header".to_string()))?;
+
+        let players = lines
+            .skip_while(|line| !line.starts_with("---"))
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(Player::from_str)
+            .collect::<Result<Vec<Player>, InvalidStatus>>()?;
+
+        Ok(ServerStatus { map, players })
+    }
+}
+
+fn take_left<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let trimmed = rest.trim_start();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let (token, remaining) = trimmed.split_at(trimmed.find(' ').unwrap_or(trimmed.len()));
+    *rest = remaining;
+    Some(token)
+}
+
+fn take_right<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let trimmed = rest.trim_end();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let (remaining, token) = trimmed.split_at(trimmed.rfind(' ').map_or(0, |i| i + 1));
+    *rest = remaining;
+    Some(token)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const STATUS_FIXTURE: &str = "map: mp_crash
+num score ping guid                             name            lastmsg address               qport rate
+--- ----- ---- -------------------------------- --------------- ------- --------------------- ----- -----
+  0    25   48 0a1b2c3d4e5f60718293a4b5c6d7e8f9 ^1Sardy^7               0 93.44.12.7:28960      31543 25000
+  1    -2  112 f9e8d7c6b5a4938271605f4e3d2c1b0a Big Bad Wolf^7          50 151.20.3.99:52120     7311 25000
+  2     0 CNCT 00000000000000000000000000000000 Newcomer^7             850 10.0.0.12:28960       4096  5000
+
+";
+
+    #[test]
+    fn test_parse_status() {
+        let status: ServerStatus = STATUS_FIXTURE.parse().unwrap();
+        assert_eq!(status.map, "mp_crash");
+        assert_eq!(status.players.len(), 3);
+        assert_eq!(
+            status.players[0],
+            Player {
+                slot: 0,
+                score: 25,
+                ping: Some(48),
+                guid: "0a1b2c3d4e5f60718293a4b5c6d7e8f9".to_string(),
+                name: "^1Sardy^7".to_string(),
+                last_msg: 0,
+                address: "93.44.12.7:28960".to_string(),
+                qport: 31543,
+                rate: 25000,
+            }
+        );
+        assert_eq!(status.players[1].name, "Big Bad Wolf^7");
+        assert_eq!(status.players[1].score, -2);
+        assert_eq!(status.players[2].ping, None);
+        assert_eq!(status.players[2].rate, 5000);
+    }
+
+    #[test]
+    fn test_parse_status_empty_server() {
+        let status: ServerStatus = "map: mp_backlot
+num score ping guid                             name            lastmsg address               qport rate
+--- ----- ---- -------------------------------- --------------- ------- --------------------- ----- -----
+"
+        .parse()
+        .unwrap();
+        assert_eq!(status.map, "mp_backlot");
+        assert!(status.players.is_empty());
+    }
+
+    #[test]
+    fn test_find_player() {
+        let mut status: ServerStatus = STATUS_FIXTURE.parse().unwrap();
+        let found = |status: &ServerStatus, target| status.find(&target).map(|p| p.slot);
+
+        assert_eq!(found(&status, PlayerTarget::Slot(2)), Some(2));
+        assert_eq!(found(&status, PlayerTarget::Slot(7)), None);
+        assert_eq!(
+            found(&status, PlayerTarget::Name("Big Bad Wolf".to_string())),
+            Some(1)
+        );
+        assert_eq!(
+            found(&status, PlayerTarget::Name("^1Sardy^7".to_string())),
+            Some(0)
+        );
+        assert_eq!(
+            found(&status, PlayerTarget::Name("big bad wolf".to_string())),
+            None
+        );
+        assert_eq!(
+            found(
+                &status,
+                PlayerTarget::Guid("F9E8D7C6B5A4938271605F4E3D2C1B0A".to_string())
+            ),
+            Some(1)
+        );
+
+        assert_eq!(
+            found(&status, PlayerTarget::Name("Sardy".to_string())),
+            Some(0)
+        );
+
+        status.players[2].name = "^2Big Bad Wolf^7".to_string();
+        assert_eq!(
+            found(&status, PlayerTarget::Name("Big Bad Wolf".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_player_target_serde() {
+        let target: PlayerTarget = serde_json::from_str(r#"{"slot": 3}"#).unwrap();
+        assert_eq!(target, PlayerTarget::Slot(3));
+        let target: PlayerTarget = serde_json::from_str(r#"{"guid": "abc"}"#).unwrap();
+        assert_eq!(target, PlayerTarget::Guid("abc".to_string()));
+        assert!(serde_json::from_str::<PlayerTarget>(r#"{"ip": "1.2.3.4"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_status_invalid() {
+        assert!("Invalid password.".parse::<ServerStatus>().is_err());
+        assert!("  0 25 48 abc".parse::<Player>().is_err());
+    }
+}
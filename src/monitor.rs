@@ -0,0 +1,183 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::db::{self, DBPool};
+use crate::fleet::Fleet;
+use crate::models::HealthSample;
+use crate::server::CodServer;
+use chrono::Utc;
+use futures_util::future::join_all;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// Last check of every game server, shared between the monitor and the API
+#[derive(Clone, Debug, Default)]
+pub struct HealthBoard {
+    current: Arc<RwLock<BTreeMap<String, HealthSample>>>,
+}
+
+impl HealthBoard {
+    // Returns the previous check, if any
+    pub fn update(&self, server_id: &str, sample: HealthSample) -> Option<HealthSample> {
+        self.current
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+            .insert(server_id.to_string(), sample)
+    }
+
+    pub fn get(&self, server_id: &str) -> Option<HealthSample> {
+        self.current
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .get(server_id)
+            .cloned()
+    }
+}
+
+pub async fn probe(server: &CodServer) -> HealthSample {
+    match server.ping().await {
+        Ok((info, round_trip)) => HealthSample {
+            time: Utc::now(),
+            online: true,
+            latency: Some(i32::try_from(round_trip.as_millis()).unwrap_or(i32::MAX)),
+            players: info.clients.map(i32::from),
+            max_players: info.max_clients.map(i32::from),
+            map: Some(info.map),
+            gametype: Some(info.gametype),
+        },
+        Err(_) => HealthSample {
+            time: Utc::now(),
+            online: false,
+            latency: None,
+            players: None,
+            max_players: None,
+            map: None,
+            gametype: None,
+        },
+    }
+}
+
+// Every server is checked at the same time, so a server that is down does
+// not delay the checks of the others. Checks older than the retention are
+// deleted once an hour.
+pub fn spawn_monitor(
+    fleet: Fleet,
+    db_pool: DBPool,
+    interval: Duration,
+    retention: chrono::Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = prune.tick() => {
+                    if let Err(error) = prune_history(&db_pool, retention).await {
+                        warn!("Error pruning health history: {}", error);
+                    }
+                    continue;
+                }
+                _ = ticker.tick() => {}
+            }
+            let definitions = fleet.definitions();
+            let samples = join_all(
+                definitions
+                    .iter()
+                    .filter_map(|definition| fleet.get(&definition.id))
+                    .map(probe),
+            )
+            .await;
+
+            for (definition, sample) in definitions.iter().zip(samples) {
+                let previous = fleet.health().update(&definition.id, sample.clone());
+                match previous.map(|previous| previous.online) {
+                    Some(true) if !sample.online => warn!("Server {} went offline", definition.id),
+                    Some(false) if sample.online => {
+                        info!("Server {} is back online", definition.id)
+                    }
+                    _ => {}
+                }
+                if let Err(error) = record(&db_pool, &definition.id, &sample).await {
+                    warn!("Error recording health of {}: {}", definition.id, error);
+                }
+            }
+        }
+    })
+}
+
+async fn prune_history(db_pool: &DBPool, retention: chrono::Duration) -> anyhow::Result<()> {
+    let before = Utc::now() - retention;
+    let pruned = db::prune_health(&db_pool.get().await?, &before).await?;
+    debug!("Pruned {} health checks", pruned);
+    Ok(())
+}
+
+async fn record(db_pool: &DBPool, server_id: &str, sample: &HealthSample) -> anyhow::Result<()> {
+    let db_client = db_pool.get().await?;
+    db::record_health(&db_client, server_id, sample).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::MockCodServer;
+
+    #[tokio::test]
+    async fn test_probe_online() {
+        let mock_server = MockCodServer::new().await;
+        let sample = probe(&CodServer::new(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+        ))
+        .await;
+        assert!(sample.online);
+        assert!(sample.latency.is_some());
+        assert_eq!(sample.map.as_deref(), Some("mp_crash"));
+        assert_eq!(sample.max_players, Some(18));
+    }
+
+    #[tokio::test]
+    async fn test_probe_offline() {
+        let mock_server = MockCodServer::silent().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            crate::rcon::RconSettings {
+                timeout: Duration::from_millis(100),
+                retries: 0,
+                ..Default::default()
+            },
+        );
+        let sample = probe(&server).await;
+        assert!(!sample.online);
+        assert_eq!(sample.latency, None);
+        assert_eq!(sample.map, None);
+
+        let board = HealthBoard::default();
+        assert_eq!(board.update("tdm", sample.clone()), None);
+        assert_eq!(board.get("tdm"), Some(sample));
+        assert_eq!(board.get("sd"), None);
+    }
+}
@@ -0,0 +1,411 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+pub(crate) const PACKET_HEADER: &[u8] = b"\xff\xff\xff\xff";
+pub(crate) const MAX_PACKET_SIZE: usize = 65536;
+
+const QUEUE_SIZE: usize = 64;
+
+#[derive(Debug)]
+pub enum RconError {
+    Timeout,
+    Unreachable(std::io::Error),
+    BadPassword,
+    MalformedReply(String),
+    UnexpectedReply(String),
+    ResponseTooLarge(usize),
+    QueueClosed,
+    InvalidArgument(String),
+    MapNotFound(String),
+    UnknownCommand(String),
+}
+
+impl Display for RconError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RconError::Timeout => write!(f, "Game server not responding"),
+            RconError::Unreachable(error) => write!(f, "Game server unreachable: {}", error),
+            RconError::BadPassword => write!(f, "Game server rejected the RCON password"),
+            RconError::MalformedReply(reply) => {
+                write!(f, "Malformed reply from game server: {}", reply)
+            }
+            RconError::UnexpectedReply(reply) => {
+                write!(f, "Unexpected reply from game server: {}", reply)
+            }
+            RconError::ResponseTooLarge(limit) => {
+                write!(f, "Reply from game server exceeds {} bytes", limit)
+            }
+            RconError::QueueClosed => write!(f, "RCON command queue is closed"),
+            RconError::InvalidArgument(reason) => write!(f, "Invalid RCON argument: {}", reason),
+            RconError::MapNotFound(map) => write!(f, "Game server cannot find map {}", map),
+            RconError::UnknownCommand(command) => {
+                write!(f, "Game server does not know the {} command", command)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RconError {}
+
+impl From<std::io::Error> for RconError {
+    fn from(error: std::io::Error) -> Self {
+        RconError::Unreachable(error)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RconSettings {
+    pub timeout: Duration,
+    pub retries: u32,
+    pub quiet_period: Duration,
+    pub max_response_size: usize,
+    pub min_interval: Duration,
+}
+
+impl Default for RconSettings {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+            retries: 2,
+            quiet_period: Duration::from_millis(150),
+            max_response_size: 256 * 1024,
+            min_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+struct Request {
+    command: String,
+    idempotent: bool,
+    reply: oneshot::Sender<Result<Reply, RconError>>,
+}
+
+// The round trip runs from the command leaving the socket to the first
+// datagram of its reply: time spent queueing, being throttled or retrying,
+// and the silence closing the reply are not part of it.
+#[derive(Debug)]
+pub struct Reply {
+    pub body: String,
+    pub round_trip: Duration,
+}
+
+// Handle to the workers that own the sockets of one game server. Clones share
+// the same workers, so commands coming from any HTTP worker are queued FIFO
+// and never reach the engine faster than its RCON throttle allows. OOB
+// queries (getinfo, getstatus...) are not covered by the throttle and have
+// a worker of their own, so they never wait behind admin commands.
+#[derive(Clone, Debug)]
+pub struct RconClient {
+    rcon: mpsc::Sender<Request>,
+    oob: mpsc::Sender<Request>,
+}
+
+impl RconClient {
+    pub fn spawn(host: &str, port: u16, settings: RconSettings) -> Self {
+        let oob_settings = RconSettings {
+            min_interval: Duration::ZERO,
+            ..settings.clone()
+        };
+        Self {
+            rcon: Worker::spawn(host, port, settings),
+            oob: Worker::spawn(host, port, oob_settings),
+        }
+    }
+
+    pub async fn send(&self, command: &str) -> Result<String, RconError> {
+        self.request(command, false).await.map(|reply| reply.body)
+    }
+
+    // Only for commands that can safely reach the server twice: a reply lost
+    // on the way back is indistinguishable from a request that never arrived.
+    // The round trip of the reply is kept for the health checks.
+    pub async fn query(&self, command: &str) -> Result<Reply, RconError> {
+        self.request(command, true).await
+    }
+
+    async fn request(&self, command: &str, idempotent: bool) -> Result<Reply, RconError> {
+        let (reply, response) = oneshot::channel();
+        let sender = if command.starts_with("rcon ") {
+            &self.rcon
+        } else {
+            &self.oob
+        };
+        sender
+            .send(Request {
+                command: command.to_string(),
+                idempotent,
+                reply,
+            })
+            .await
+            .map_err(|_| RconError::QueueClosed)?;
+        response.await.map_err(|_| RconError::QueueClosed)?
+    }
+}
+
+struct Worker {
+    host: String,
+    port: u16,
+    settings: RconSettings,
+    socket: Option<UdpSocket>,
+    last_sent: Option<Instant>,
+    buf: Vec<u8>,
+}
+
+impl Worker {
+    fn spawn(host: &str, port: u16, settings: RconSettings) -> mpsc::Sender<Request> {
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        let worker = Worker {
+            host: host.to_string(),
+            port,
+            settings,
+            socket: None,
+            last_sent: None,
+            buf: vec![0; MAX_PACKET_SIZE],
+        };
+        tokio::spawn(worker.run(receiver));
+        sender
+    }
+
+    async fn run(mut self, mut receiver: mpsc::Receiver<Request>) {
+        while let Some(request) = receiver.recv().await {
+            let result = self.execute(&request.command, request.idempotent).await;
+            if request.reply.send(result).is_err() {
+                debug!("Caller of {}:{} went away", self.host, self.port);
+            }
+        }
+    }
+
+    async fn execute(&mut self, command: &str, idempotent: bool) -> Result<Reply, RconError> {
+        let retries = if idempotent { self.settings.retries } else { 0 };
+        let mut attempt = 0;
+        loop {
+            match self.exchange(command).await {
+                Err(RconError::Timeout) if attempt < retries => {
+                    attempt += 1;
+                    warn!(
+                        "No reply from {}:{}, retrying ({}/{})",
+                        self.host, self.port, attempt, retries
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn exchange(&mut self, command: &str) -> Result<Reply, RconError> {
+        let socket = match self.socket.take() {
+            Some(socket) => socket,
+            None => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                socket.connect((self.host.as_str(), self.port)).await?;
+                socket
+            }
+        };
+        let result = self.exchange_on(&socket, command).await;
+        // A reply may still be on its way after a timeout. The socket is
+        // dropped with it, so that it lands on a closed port instead of
+        // being taken for the answer to the next command.
+        if !matches!(result, Err(RconError::Timeout)) {
+            self.socket = Some(socket);
+        }
+        result
+    }
+
+    // Long replies are split by the engine into several datagrams, each one
+    // carrying its own "print" header; they keep coming until the server has
+    // nothing left to say, so the end of a reply is detected by silence.
+    async fn exchange_on(&mut self, socket: &UdpSocket, command: &str) -> Result<Reply, RconError> {
+        if let Some(last_sent) = self.last_sent {
+            tokio::time::sleep_until(last_sent + self.settings.min_interval).await;
+        }
+
+        // Nothing unsolicited is expected, whatever is waiting is stale
+        while socket.try_recv(&mut self.buf).is_ok() {}
+
+        let mut payload = PACKET_HEADER.to_vec();
+        payload.extend_from_slice(command.as_bytes());
+        socket.send(&payload).await?;
+        let sent = Instant::now();
+        self.last_sent = Some(sent);
+
+        let len = tokio::time::timeout(self.settings.timeout, socket.recv(&mut self.buf))
+            .await
+            .map_err(|_| RconError::Timeout)??;
+        let round_trip = sent.elapsed();
+        let mut response = packet_data(&self.buf[..len])?.to_vec();
+
+        loop {
+            let Ok(received) =
+                tokio::time::timeout(self.settings.quiet_period, socket.recv(&mut self.buf)).await
+            else {
+                break;
+            };
+            let data = packet_data(&self.buf[..received?])?;
+            let data = data.strip_prefix(b"print\n").unwrap_or(data);
+            if response.len() + data.len() > self.settings.max_response_size {
+                return Err(RconError::ResponseTooLarge(self.settings.max_response_size));
+            }
+            response.extend_from_slice(data);
+        }
+
+        Ok(Reply {
+            body: String::from_utf8_lossy(&response).to_string(),
+            round_trip,
+        })
+    }
+}
+
+fn packet_data(packet: &[u8]) -> Result<&[u8], RconError> {
+    match packet.strip_prefix(PACKET_HEADER) {
+        Some(data) if !data.is_empty() => Ok(data.strip_prefix(b"\n").unwrap_or(data)),
+        _ => Err(RconError::MalformedReply(
+            String::from_utf8_lossy(packet).to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::MockCodServer;
+
+    fn settings(min_interval: u64) -> RconSettings {
+        RconSettings {
+            timeout: Duration::from_millis(100),
+            retries: 0,
+            quiet_period: Duration::from_millis(50),
+            min_interval: Duration::from_millis(min_interval),
+            ..RconSettings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_min_interval() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let client = RconClient::spawn("127.0.0.1", mock_server.port(), settings(300));
+        let start = Instant::now();
+        client.send("rcon test_password test_command 1").await?;
+        client.send("rcon test_password test_command 2").await?;
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_oob_not_throttled() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let client = RconClient::spawn("127.0.0.1", mock_server.port(), settings(300));
+        client.send("rcon test_password test_command 1").await?;
+        let start = Instant::now();
+        client.query("getinfo olg").await?;
+        client.query("getstatus").await?;
+        assert!(start.elapsed() < Duration::from_millis(300));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let client = RconClient::spawn("127.0.0.1", mock_server.port(), settings(300));
+        let start = Instant::now();
+        client.send("rcon test_password test_command 1").await?;
+        let reply = client.query("rcon test_password test_command 2").await?;
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert!(reply.round_trip < Duration::from_millis(50));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_commands() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let client = RconClient::spawn("127.0.0.1", mock_server.port(), settings(10));
+        let (first, second, third) = tokio::join!(
+            client.send("test_command 1"),
+            client.send("test_command 2"),
+            client.send("test_command 3"),
+        );
+        assert_eq!(first?, "test_command 1");
+        assert_eq!(second?, "test_command 2");
+        assert_eq!(third?, "test_command 3");
+        assert_eq!(mock_server.payload_size().await, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stale_reply_discarded() -> anyhow::Result<()> {
+        let server = UdpSocket::bind("127.0.0.1:0").await?;
+        let port = server.local_addr()?.port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let (_, addr) = server.recv_from(&mut buf).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            server
+                .send_to(b"\xff\xff\xff\xffprint\nfirst", addr)
+                .await
+                .unwrap();
+            let (_, addr) = server.recv_from(&mut buf).await.unwrap();
+            server
+                .send_to(b"\xff\xff\xff\xffprint\nsecond", addr)
+                .await
+                .unwrap();
+        });
+
+        let client = RconClient::spawn("127.0.0.1", port, settings(400));
+        assert!(matches!(
+            client.send("first").await,
+            Err(RconError::Timeout)
+        ));
+        assert_eq!(client.send("second").await?, "print\nsecond");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_late_reply_discarded() -> anyhow::Result<()> {
+        let server = UdpSocket::bind("127.0.0.1:0").await?;
+        let port = server.local_addr()?.port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let (_, first) = server.recv_from(&mut buf).await.unwrap();
+            let (_, second) = server.recv_from(&mut buf).await.unwrap();
+            // The reply to the first command arrives while the second one is
+            // waiting for its own
+            server
+                .send_to(b"\xff\xff\xff\xffprint\nfirst", first)
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            server
+                .send_to(b"\xff\xff\xff\xffprint\nsecond", second)
+                .await
+                .unwrap();
+        });
+
+        let client = RconClient::spawn("127.0.0.1", port, settings(10));
+        assert!(matches!(
+            client.send("first").await,
+            Err(RconError::Timeout)
+        ));
+        assert_eq!(client.send("second").await?, "print\nsecond");
+        Ok(())
+    }
+}
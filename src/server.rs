@@ -15,15 +15,31 @@
  *
  */
 
-use crate::models::Gametype;
+use crate::command::RconCommand;
+use crate::game::{Game, GameServer};
+use crate::metrics;
+use crate::models::{
+    Dvar, GametypeDefinition, Player, PlayerTarget, QueryStatus, ServerInfo, ServerStatus,
+    split_message,
+};
+use crate::rcon::{RconClient, RconError, RconSettings, Reply};
 use std::fmt::{Display, Formatter};
-use tokio::net::UdpSocket;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// Longer chat lines are truncated by the engine
+const MAX_MESSAGE_LENGTH: usize = 120;
 
 #[derive(Clone, Debug)]
 pub struct CodServer {
+    // Name of the server in metrics, its address unless told otherwise
+    id: String,
+    game: &'static dyn GameServer,
     host: String,
     port: u16,
     rcon_password: String,
+    client: RconClient,
 }
 
 impl Display for CodServer {
@@ -33,80 +49,347 @@ impl Display for CodServer {
 }
 
 impl CodServer {
+    // Default settings, the servers of the fleet get the configured ones
+    #[cfg(test)]
     pub fn new(host: &str, port: u16, rcon_password: &str) -> Self {
+        Self::with_settings(host, port, rcon_password, RconSettings::default())
+    }
+
+    // The RCON worker is spawned here, once for the lifetime of the server
+    pub fn with_settings(
+        host: &str,
+        port: u16,
+        rcon_password: &str,
+        settings: RconSettings,
+    ) -> Self {
         Self {
+            id: format!("{}:{}", host, port),
+            game: Game::default().server(),
             host: host.to_string(),
             port,
             rcon_password: rcon_password.to_string(),
+            client: RconClient::spawn(host, port, settings),
         }
     }
 
-    pub async fn map_restart(&self) -> anyhow::Result<()> {
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = id.to_string();
+        self
+    }
+
+    pub fn with_game(mut self, game: Game) -> Self {
+        self.game = game.server();
+        self
+    }
+
+    // Stock gametypes have to be the ones of the title. Those of a mod are
+    // left to the server, nothing here tells which mod it runs.
+    pub fn supports_gametype(&self, gametype: &GametypeDefinition) -> bool {
+        gametype.source_mod.is_some() || self.game.gametypes().contains(&gametype.tag.to_tag())
+    }
+
+    pub async fn map_restart(&self) -> Result<(), RconError> {
         let response = self.rcon("map_restart").await?;
-        if !response.starts_with("print\n==== ShutdownGame") {
-            return Err(anyhow::anyhow!("Failed to restart map: {}", response));
+        if !self.game.is_map_loading(&response) {
+            return Err(RconError::UnexpectedReply(response));
         }
         Ok(())
     }
 
-    pub async fn fast_restart(&self) -> anyhow::Result<()> {
+    pub async fn fast_restart(&self) -> Result<(), RconError> {
         let response = self.rcon("fast_restart").await?;
-        if !response.starts_with("print\n==== ShutdownGame") {
-            return Err(anyhow::anyhow!("Failed to restart map: {}", response));
+        if !self.game.is_map_loading(&response) {
+            return Err(RconError::UnexpectedReply(response));
         }
         Ok(())
     }
 
-    pub async fn gametype_map(&self, gametype: &Gametype, map: &str) -> anyhow::Result<()> {
-        let cmd = format!("g_gametype {}", gametype.to_tag());
-        self.rcon(&cmd).await?;
+    pub async fn map_rotate(&self) -> Result<(), RconError> {
+        let response = self.rcon("map_rotate").await?;
+        if !self.game.is_map_loading(&response) {
+            return Err(RconError::UnexpectedReply(response));
+        }
+        Ok(())
+    }
+
+    // Resetting sv_mapRotationCurrent makes the next map_rotate start from
+    // the first entry of the new rotation instead of resuming the old one.
+    pub async fn set_map_rotation(&self, rotation: &str) -> Result<(), RconError> {
+        let cmd = RconCommand::new("set")
+            .word("sv_mapRotation")?
+            .quoted(rotation)?;
+        self.rcon(&cmd.to_string()).await?;
+        let cmd = RconCommand::new("set")
+            .word("sv_mapRotationCurrent")?
+            .quoted("")?;
+        self.rcon(&cmd.to_string()).await?;
+        Ok(())
+    }
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    // A missing map leaves the current one running, so the gametype it runs
+    // with is put back rather than being picked up by the next map change.
+    // When that gametype cannot be read the map is changed all the same, with
+    // nothing to put back.
+    pub async fn gametype_map(&self, gametype: &str, map: &str) -> Result<(), RconError> {
+        // Everything is built first, so that a bad map changes nothing
+        let gametype_cmd = RconCommand::new("g_gametype").word(gametype)?;
+        let map_cmd = RconCommand::new("map").word(map)?;
+        let restore_cmd = match self.get_info().await {
+            Ok(info) => RconCommand::new("g_gametype").word(&info.gametype).ok(),
+            Err(error) => {
+                warn!("Current gametype of {} unknown: {}", self.id, error);
+                None
+            }
+        };
+        self.rcon(&gametype_cmd.to_string()).await?;
 
-        let cmd = format!("map {}", map);
-        let response = self.rcon(&cmd).await?;
-        if !response.starts_with("print\n==== ShutdownGame") {
-            return Err(anyhow::anyhow!("Failed to change map: {}", response));
+        let response = self.rcon(&map_cmd.to_string()).await?;
+        if self.game.is_missing_map(&response) {
+            if let Some(restore_cmd) = restore_cmd {
+                self.rcon(&restore_cmd.to_string()).await?;
+            }
+            return Err(RconError::MapNotFound(map.to_string()));
+        }
+        if !self.game.is_map_loading(&response) {
+            return Err(RconError::UnexpectedReply(response));
         }
 
         Ok(())
     }
 
-    async fn rcon(&self, command: &str) -> anyhow::Result<String> {
+    pub async fn status(&self) -> Result<ServerStatus, RconError> {
+        let response = self.rcon_query("status").await?;
+        match response
+            .strip_prefix("print\n")
+            .map(|body| self.game.parse_status(body))
+        {
+            Some(Some(status)) => Ok(status),
+            _ => Err(RconError::MalformedReply(response)),
+        }
+    }
+
+    pub async fn get_info(&self) -> Result<ServerInfo, RconError> {
+        self.ping().await.map(|(info, _)| info)
+    }
+
+    // getinfo along with the round trip of its reply, used as the latency of
+    // the server by the health checks
+    pub async fn ping(&self) -> Result<(ServerInfo, Duration), RconError> {
+        let reply = self.query_reply("getinfo olg").await?;
+        let info = Self::parse_reply(reply.body, "infoResponse\n")?;
+        Ok((info, reply.round_trip))
+    }
+
+    pub async fn get_status(&self) -> Result<QueryStatus, RconError> {
+        let response = self.query("getstatus").await?;
+        Self::parse_reply(response, "statusResponse\n")
+    }
+
+    pub async fn get_dvar(&self, name: &str) -> Result<Option<Dvar>, RconError> {
+        let cmd = RconCommand::dvar(name)?;
+        let response = self.rcon_query(&cmd.to_string()).await?;
+        let Some(body) = response.strip_prefix("print\n") else {
+            return Err(RconError::MalformedReply(response));
+        };
+        if body.starts_with("Unknown command") {
+            return Ok(None);
+        }
+        match body.parse::<Dvar>() {
+            Ok(dvar) => Ok(Some(dvar)),
+            Err(_) => Err(RconError::MalformedReply(response)),
+        }
+    }
+
+    pub async fn set_dvar(&self, name: &str, value: &str) -> Result<Dvar, RconError> {
+        let cmd = RconCommand::new("set").word(name)?.quoted(value)?;
+        self.rcon(&cmd.to_string()).await?;
+        self.get_dvar(name)
+            .await?
+            .ok_or_else(|| RconError::UnexpectedReply(format!("{} was not set", name)))
+    }
+
+    pub async fn kick(&self, target: &PlayerTarget) -> Result<Option<Player>, RconError> {
+        self.act_on_player(target, "clientkick", "kick").await
+    }
+
+    pub async fn temp_ban(&self, target: &PlayerTarget) -> Result<Option<Player>, RconError> {
+        self.act_on_player(target, "tempBanClient", "tempBanUser")
+            .await
+    }
+
+    pub async fn ban(&self, target: &PlayerTarget) -> Result<Option<Player>, RconError> {
+        self.act_on_player(target, "banClient", "banUser").await
+    }
+
+    pub async fn unban(&self, name: &str) -> Result<(), RconError> {
+        let cmd = RconCommand::new("unban").quoted(name)?;
+        self.rcon(&cmd.to_string()).await?;
+        Ok(())
+    }
+
+    pub async fn say(&self, message: &str) -> Result<(), RconError> {
+        for line in split_message(message, MAX_MESSAGE_LENGTH) {
+            let cmd = RconCommand::new("say").quoted(&line)?;
+            self.rcon(&cmd.to_string()).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn tell(
+        &self,
+        target: &PlayerTarget,
+        message: &str,
+    ) -> Result<Option<Player>, RconError> {
+        let status = self.status().await?;
+        let Some(player) = status.find(target) else {
+            return Ok(None);
+        };
+        for line in split_message(message, MAX_MESSAGE_LENGTH) {
+            let cmd = RconCommand::new("tell").number(player.slot).quoted(&line)?;
+            self.rcon(&cmd.to_string()).await?;
+        }
+        Ok(Some(player.clone()))
+    }
+
+    // screensay prints in the middle of every screen; it is not available on
+    // stock 1.7 servers, only on CoD4x ones. A stock server answers the first
+    // line with an unknown command, the rest of the message is not sent then.
+    pub async fn announce(&self, message: &str) -> Result<(), RconError> {
+        for line in split_message(message, MAX_MESSAGE_LENGTH) {
+            let cmd = RconCommand::new("screensay").quoted(&line)?;
+            let response = self.rcon(&cmd.to_string()).await?;
+            if response
+                .strip_prefix("print\n")
+                .is_some_and(|body| body.starts_with("Unknown command"))
+            {
+                return Err(RconError::UnknownCommand("screensay".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    // Raw reply of an arbitrary command, without the "print" header. It is
+    // sent as it is: callers are expected to have vetted it already.
+    // Console commands are free text, so their metrics all go under one name
+    pub async fn console(&self, command: &str) -> Result<String, RconError> {
         let rcon_command = format!("rcon {} {}", self.rcon_password, command);
-        self.send(&rcon_command).await
+        let response = Self::check_password(self.send_as(&rcon_command, "console").await?)?;
+        Ok(response
+            .strip_prefix("print\n")
+            .map(str::to_string)
+            .unwrap_or(response))
     }
 
-    async fn send(&self, command: &str) -> anyhow::Result<String> {
-        let mut payload = vec![0xff, 0xff, 0xff, 0xff];
-        payload.extend_from_slice(command.as_bytes());
+    // The command reaches the engine a while after status, the RCON throttle
+    // sits in between. Name-based variants act on whoever has the name by
+    // then, so a player leaving is never mistaken for the one taking their
+    // slot. Slots are only used when the name is shared or cannot be quoted,
+    // and only once the GUID on the slot has been checked again.
+    async fn act_on_player(
+        &self,
+        target: &PlayerTarget,
+        by_slot: &'static str,
+        by_name: &'static str,
+    ) -> Result<Option<Player>, RconError> {
+        let status = self.status().await?;
+        let Some(player) = status.find(target).cloned() else {
+            return Ok(None);
+        };
+        let unique_name = status
+            .find(&PlayerTarget::Name(player.name.clone()))
+            .is_some();
+        let cmd = match RconCommand::new(by_name).quoted(&player.name) {
+            Ok(cmd) if unique_name => cmd,
+            _ => {
+                let status = self.status().await?;
+                let same_player = status
+                    .find(&PlayerTarget::Slot(player.slot))
+                    .is_some_and(|current| current.guid == player.guid);
+                if !same_player {
+                    return Ok(None);
+                }
+                RconCommand::new(by_slot).number(player.slot)
+            }
+        };
+        self.rcon(&cmd.to_string()).await?;
+        Ok(Some(player))
+    }
+
+    fn parse_reply<T: FromStr>(response: String, prefix: &str) -> Result<T, RconError> {
+        match response.strip_prefix(prefix).map(str::parse::<T>) {
+            Some(Ok(parsed)) => Ok(parsed),
+            _ => Err(RconError::MalformedReply(response)),
+        }
+    }
 
-        let socket = UdpSocket::bind("0.0.0.0:0").await?;
-        let server_addr = format!("{}:{}", self.host, self.port);
-        socket.send_to(&payload, &server_addr).await?;
+    async fn rcon(&self, command: &str) -> Result<String, RconError> {
+        let rcon_command = format!("rcon {} {}", self.rcon_password, command);
+        Self::check_password(self.send(&rcon_command).await?)
+    }
 
-        let mut buf = [0; 4096];
-        let (len, _) = socket.recv_from(&mut buf).await?;
+    async fn rcon_query(&self, command: &str) -> Result<String, RconError> {
+        let rcon_command = format!("rcon {} {}", self.rcon_password, command);
+        Self::check_password(self.query(&rcon_command).await?)
+    }
 
-        if len > 4 && &buf[..4] == b"\xff\xff\xff\xff" {
-            let response_data = &buf[4..len];
-            let response = if response_data.starts_with(b"\n") {
-                &response_data[1..]
-            } else {
-                response_data
-            };
-            Ok(String::from_utf8_lossy(response).to_string())
-        } else {
-            Err(anyhow::anyhow!("Invalid or empty response from server"))
+    fn check_password(response: String) -> Result<String, RconError> {
+        match response.strip_prefix("print\n").map(str::trim_end) {
+            Some(
+                "Invalid password." | "Bad rconpassword." | "No rconpassword set on the server.",
+            ) => Err(RconError::BadPassword),
+            _ => Ok(response),
         }
     }
+
+    async fn query(&self, command: &str) -> Result<String, RconError> {
+        self.query_reply(command).await.map(|reply| reply.body)
+    }
+
+    async fn query_reply(&self, command: &str) -> Result<Reply, RconError> {
+        let started = Instant::now();
+        let result = self.client.query(command).await;
+        metrics::observe_rcon(
+            &self.id,
+            self.command_name(command),
+            started.elapsed(),
+            &result,
+        );
+        result
+    }
+
+    async fn send(&self, command: &str) -> Result<String, RconError> {
+        self.send_as(command, self.command_name(command)).await
+    }
+
+    async fn send_as(&self, command: &str, name: &str) -> Result<String, RconError> {
+        let started = Instant::now();
+        let result = self.client.send(command).await;
+        metrics::observe_rcon(&self.id, name, started.elapsed(), &result);
+        result
+    }
+
+    // Only the name of the command is kept: arguments would create a series
+    // for every value, and RCON ones start with the password.
+    fn command_name<'a>(&self, command: &'a str) -> &'a str {
+        let rcon_prefix = format!("rcon {} ", self.rcon_password);
+        command
+            .strip_prefix(&rcon_prefix)
+            .unwrap_or(command)
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use std::collections::VecDeque;
+    use crate::models::{GETINFO_FIXTURE, GETSTATUS_FIXTURE, STATUS_FIXTURE};
+    use crate::rcon::{MAX_PACKET_SIZE, PACKET_HEADER};
+    use std::collections::{HashMap, VecDeque};
     use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::UdpSocket;
     use tokio::sync::RwLock;
 
     pub(crate) struct MockCodServer {
@@ -116,18 +399,52 @@ pub(crate) mod tests {
 
     impl MockCodServer {
         pub async fn new() -> Self {
+            Self::with_packet_size(MAX_PACKET_SIZE).await
+        }
+
+        // Splits "print" replies into several datagrams of at most
+        // `packet_size` bytes of body each, like the engine does.
+        pub async fn with_packet_size(packet_size: usize) -> Self {
+            Self::start(packet_size, true, true).await
+        }
+
+        // Records every payload but never answers, like a server that is down.
+        pub async fn silent() -> Self {
+            Self::start(MAX_PACKET_SIZE, false, false).await
+        }
+
+        // Answers RCON but not getinfo, like a server throttling queries
+        pub async fn without_info() -> Self {
+            Self::start(MAX_PACKET_SIZE, true, false).await
+        }
+
+        async fn start(packet_size: usize, reply: bool, info: bool) -> Self {
             let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
             let payloads: Arc<RwLock<VecDeque<Vec<u8>>>> = Arc::new(RwLock::new(VecDeque::new()));
 
             tokio::spawn({
                 let socket = socket.clone();
                 let payloads = payloads.clone();
+                let mut dvars =
+                    HashMap::from([("sv_hostname".to_string(), "^1OLG ^7Public TDM".to_string())]);
                 async move {
                     let mut buf = [0u8; 4096];
                     loop {
                         let (len, addr) = socket.recv_from(&mut buf).await.unwrap();
                         let payload = &buf[..len];
                         payloads.write().await.push_back(payload.to_vec());
+                        if !reply || (!info && payload.starts_with(b"\xff\xff\xff\xffgetinfo")) {
+                            continue;
+                        }
+                        if payload.starts_with(b"\xff\xff\xff\xffrcon ")
+                            && !payload.starts_with(b"\xff\xff\xff\xffrcon test_password ")
+                        {
+                            socket
+                                .send_to(b"\xff\xff\xff\xffprint\nInvalid password.\n", &addr)
+                                .await
+                                .unwrap();
+                            continue;
+                        }
                         if payload
                             .windows(b"test_command".len())
                             .any(|window| window == b"test_command")
@@ -135,6 +452,48 @@ pub(crate) mod tests {
                             socket.send_to(payload, &addr).await.unwrap();
                             continue;
                         }
+                        let command = payload
+                            .strip_prefix(b"\xff\xff\xff\xffrcon test_password ")
+                            .map(|command| String::from_utf8_lossy(command).to_string());
+                        let fixture = if payload.ends_with(b" status") {
+                            Some(("print", STATUS_FIXTURE.to_string()))
+                        } else if payload.ends_with(b" map mp_missing") {
+                            Some((
+                                "print",
+                                "Can't find map maps/mp/mp_missing.d3dbsp\n".to_string(),
+                            ))
+                        } else if payload
+                            .starts_with(b"\xff\xff\xff\xffrcon test_password screensay ")
+                        {
+                            // Like a stock 1.7 server
+                            Some(("print", "Unknown command \"screensay\"\n".to_string()))
+                        } else if payload.starts_with(b"\xff\xff\xff\xffgetinfo") {
+                            Some(("infoResponse", GETINFO_FIXTURE.to_string()))
+                        } else if payload.starts_with(b"\xff\xff\xff\xffgetstatus") {
+                            Some(("statusResponse", GETSTATUS_FIXTURE.to_string()))
+                        } else {
+                            command
+                                .and_then(|command| Self::dvar_reply(&command, &mut dvars))
+                                .map(|reply| ("print", reply))
+                        };
+                        if let Some((kind, body)) = fixture {
+                            let chunk_size = if kind == "print" {
+                                packet_size
+                            } else {
+                                body.len()
+                            };
+                            let chunks = match body.is_empty() {
+                                true => vec![&[][..]],
+                                false => body.as_bytes().chunks(chunk_size).collect(),
+                            };
+                            for chunk in chunks {
+                                let mut response = PACKET_HEADER.to_vec();
+                                response.extend_from_slice(format!("{}\n", kind).as_bytes());
+                                response.extend_from_slice(chunk);
+                                socket.send_to(&response, &addr).await.unwrap();
+                            }
+                            continue;
+                        }
                         socket
                             .send_to(b"\xff\xff\xff\xffprint\n==== ShutdownGame", &addr)
                             .await
@@ -146,6 +505,26 @@ pub(crate) mod tests {
             Self { socket, payloads }
         }
 
+        fn dvar_reply(command: &str, dvars: &mut HashMap<String, String>) -> Option<String> {
+            if let Some(assignment) = command.strip_prefix("set ") {
+                let (name, value) = assignment.split_once(' ')?;
+                dvars.insert(name.to_lowercase(), value.trim_matches('"').to_string());
+                return Some(String::new());
+            }
+            if command.contains(' ')
+                || ["map_restart", "fast_restart", "map_rotate"].contains(&command)
+            {
+                return None;
+            }
+            Some(match dvars.get(&command.to_lowercase()) {
+                Some(value) => format!(
+                    "\"{}\" is:\"{}^7\" default:\"CoD4Host^7\"\n",
+                    command, value
+                ),
+                None => format!("Unknown command \"{}\"\n", command),
+            })
+        }
+
         pub fn port(&self) -> u16 {
             self.socket.local_addr().unwrap().port()
         }
@@ -159,8 +538,8 @@ pub(crate) mod tests {
         }
     }
 
-    #[test]
-    fn test_new() {
+    #[tokio::test]
+    async fn test_new() {
         let server = CodServer::new("localhost", 28960, "my_rcon_password");
         assert_eq!(server.host, "localhost");
         assert_eq!(server.port, 28960);
@@ -185,6 +564,101 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_console() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let reply = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .console("status")
+            .await?;
+        assert_eq!(reply, STATUS_FIXTURE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_supports_gametype() {
+        let server = CodServer::new("127.0.0.1", 28960, "test_password");
+        let mut gametype = GametypeDefinition {
+            tag: "war".parse().unwrap(),
+            name: "Team Deathmatch".to_string(),
+            description: None,
+            team_based: true,
+            source_mod: None,
+        };
+        assert!(server.supports_gametype(&gametype));
+        gametype.tag = "ctf".parse().unwrap();
+        assert!(!server.supports_gametype(&gametype));
+        gametype.source_mod = Some("promod".to_string());
+        assert!(server.supports_gametype(&gametype));
+    }
+
+    #[tokio::test]
+    async fn test_gametype_map_missing() {
+        let mock_server = MockCodServer::new().await;
+        let result = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .gametype_map("sd", "mp_missing")
+            .await;
+        assert!(matches!(result, Err(RconError::MapNotFound(map)) if map == "mp_missing"));
+        // The gametype of the map still running is put back
+        assert_eq!(mock_server.payload_size().await, 4);
+        let mut last = None;
+        while let Some(payload) = mock_server.read_payload().await {
+            last = Some(payload);
+        }
+        assert_eq!(
+            last,
+            Some(b"\xff\xff\xff\xffrcon test_password g_gametype war".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gametype_map_without_info() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::without_info().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            RconSettings {
+                timeout: Duration::from_millis(100),
+                retries: 0,
+                ..RconSettings::default()
+            },
+        );
+        server.gametype_map("sd", "mp_crash").await?;
+        let result = server.gametype_map("sd", "mp_missing").await;
+        assert!(matches!(result, Err(RconError::MapNotFound(_))));
+        // getinfo, g_gametype and map twice, nothing put back
+        assert_eq!(mock_server.payload_size().await, 6);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_injection_never_sent() {
+        let mock_server = MockCodServer::new().await;
+        let server = CodServer::new("127.0.0.1", mock_server.port(), "test_password");
+        for result in [
+            server
+                .gametype_map("war", "mp_crash; rcon_password x")
+                .await,
+            server.gametype_map("war", "mp_crash\nquit").await,
+            server
+                .set_map_rotation("gametype war map mp_crash\"; quit")
+                .await,
+            server.set_dvar("sv_hostname;quit", "x").await.map(|_| ()),
+            server
+                .set_dvar("sv_hostname", "x\"; quit; \"")
+                .await
+                .map(|_| ()),
+            server.unban("Sardy\"; quit").await,
+        ] {
+            assert!(matches!(result, Err(RconError::InvalidArgument(_))));
+        }
+        assert!(matches!(
+            server.get_dvar("sv_hostname\nquit").await,
+            Err(RconError::InvalidArgument(_))
+        ));
+        assert_eq!(mock_server.payload_size().await, 0);
+    }
+
     #[tokio::test]
     async fn test_map_restart() -> anyhow::Result<()> {
         let mock_server = MockCodServer::new().await;
@@ -217,9 +691,13 @@ pub(crate) mod tests {
     async fn test_gametype_map() -> anyhow::Result<()> {
         let mock_server = MockCodServer::new().await;
         CodServer::new("127.0.0.1", mock_server.port(), "test_password")
-            .gametype_map(&Gametype::SearchAndDestroy, "mp_crash")
+            .gametype_map("sd", "mp_crash")
             .await?;
-        assert_eq!(mock_server.payload_size().await, 2);
+        assert_eq!(mock_server.payload_size().await, 3);
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffgetinfo olg".to_vec())
+        );
         assert_eq!(
             mock_server.read_payload().await,
             Some(b"\xff\xff\xff\xffrcon test_password g_gametype sd".to_vec())
@@ -230,4 +708,316 @@ pub(crate) mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_status() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let status = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .status()
+            .await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password status".to_vec())
+        );
+        assert_eq!(status.map, "mp_crash");
+        assert_eq!(status.players.len(), 3);
+        assert_eq!(status.players[1].name, "Big Bad Wolf^7");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_info() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let info = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .get_info()
+            .await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffgetinfo olg".to_vec())
+        );
+        assert_eq!(info.map, "mp_crash");
+        assert_eq!(info.max_clients, Some(18));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_status() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let status = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .get_status()
+            .await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffgetstatus".to_vec())
+        );
+        assert_eq!(status.info.gametype, "war");
+        assert_eq!(status.players.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_status_multi_packet() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::with_packet_size(64).await;
+        let status = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .status()
+            .await?;
+        assert_eq!(status, STATUS_FIXTURE.parse::<ServerStatus>()?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_max_response_size() {
+        let mock_server = MockCodServer::with_packet_size(64).await;
+        let settings = RconSettings {
+            max_response_size: 128,
+            ..RconSettings::default()
+        };
+        let result =
+            CodServer::with_settings("127.0.0.1", mock_server.port(), "test_password", settings)
+                .status()
+                .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_retries() {
+        let mock_server = MockCodServer::silent().await;
+        let settings = RconSettings {
+            timeout: Duration::from_millis(100),
+            retries: 2,
+            min_interval: Duration::from_millis(10),
+            ..RconSettings::default()
+        };
+        let server =
+            CodServer::with_settings("127.0.0.1", mock_server.port(), "test_password", settings);
+
+        let result = server.status().await;
+        assert!(matches!(result, Err(RconError::Timeout)));
+        assert_eq!(mock_server.payload_size().await, 3);
+
+        let result = server.map_restart().await;
+        assert!(matches!(result, Err(RconError::Timeout)));
+        assert_eq!(mock_server.payload_size().await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_bad_password() {
+        let mock_server = MockCodServer::new().await;
+        let result = CodServer::new("127.0.0.1", mock_server.port(), "wrong_password")
+            .map_restart()
+            .await;
+        assert!(matches!(result, Err(RconError::BadPassword)));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_reply() {
+        let mock_server = MockCodServer::new().await;
+        let result = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .rcon("test_command")
+            .await
+            .map(|response| CodServer::parse_reply::<ServerStatus>(response, "print\n"));
+        assert!(matches!(result, Ok(Err(RconError::MalformedReply(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_get_dvar() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let server = CodServer::new("127.0.0.1", mock_server.port(), "test_password");
+        let dvar = server.get_dvar("sv_hostname").await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password sv_hostname".to_vec())
+        );
+        assert_eq!(
+            dvar,
+            Some(Dvar {
+                name: "sv_hostname".to_string(),
+                value: "^1OLG ^7Public TDM".to_string(),
+                default: Some("CoD4Host".to_string()),
+            })
+        );
+        assert_eq!(server.get_dvar("sv_unknown").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_dvar() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            RconSettings {
+                min_interval: Duration::from_millis(10),
+                ..RconSettings::default()
+            },
+        );
+        let dvar = server.set_dvar("scr_sd_timelimit", "2.5").await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password set scr_sd_timelimit \"2.5\"".to_vec())
+        );
+        assert_eq!(dvar.value, "2.5");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_kick() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            RconSettings {
+                min_interval: Duration::from_millis(10),
+                ..RconSettings::default()
+            },
+        );
+        let player = server
+            .kick(&PlayerTarget::Guid(
+                "f9e8d7c6b5a4938271605f4e3d2c1b0a".to_string(),
+            ))
+            .await?;
+        assert_eq!(player.map(|player| player.slot), Some(1));
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password status".to_vec())
+        );
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password kick \"Big Bad Wolf^7\"".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ban_unknown_player() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let player = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .ban(&PlayerTarget::Name("Nobody".to_string()))
+            .await?;
+        assert_eq!(player, None);
+        assert_eq!(mock_server.payload_size().await, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_temp_ban_and_unban() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            RconSettings {
+                min_interval: Duration::from_millis(10),
+                ..RconSettings::default()
+            },
+        );
+        server.temp_ban(&PlayerTarget::Slot(0)).await?;
+        server.unban("Sardy").await?;
+        mock_server.read_payload().await;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password tempBanUser \"^1Sardy^7\"".to_vec())
+        );
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password unban \"Sardy\"".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_say() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .say("^1Server restart in 5 minutes")
+            .await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(
+                b"\xff\xff\xff\xffrcon test_password say \"^1Server restart in 5 minutes\""
+                    .to_vec()
+            )
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_announce_unknown_command() {
+        let mock_server = MockCodServer::new().await;
+        let message = "word ".repeat(40);
+        let result = CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .announce(&message)
+            .await;
+        assert!(
+            matches!(result, Err(RconError::UnknownCommand(command)) if command == "screensay")
+        );
+        assert_eq!(mock_server.payload_size().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_tell_long_message() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        let server = CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            RconSettings {
+                min_interval: Duration::from_millis(10),
+                ..RconSettings::default()
+            },
+        );
+        let message = "word ".repeat(40);
+        let player = server
+            .tell(&PlayerTarget::Name("^1Sardy".to_string()), &message)
+            .await?;
+        assert_eq!(player.map(|player| player.slot), Some(0));
+        assert_eq!(mock_server.payload_size().await, 3);
+        mock_server.read_payload().await;
+        let payload = mock_server.read_payload().await.unwrap_or_default();
+        assert!(payload.starts_with(b"\xff\xff\xff\xffrcon test_password tell 0 \"word word"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_map_rotate() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        CodServer::new("127.0.0.1", mock_server.port(), "test_password")
+            .map_rotate()
+            .await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password map_rotate".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_map_rotation() -> anyhow::Result<()> {
+        let mock_server = MockCodServer::new().await;
+        CodServer::with_settings(
+            "127.0.0.1",
+            mock_server.port(),
+            "test_password",
+            RconSettings {
+                min_interval: Duration::from_millis(10),
+                ..RconSettings::default()
+            },
+        )
+        .set_map_rotation("gametype war map mp_crash gametype sd map mp_backlot")
+        .await?;
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(
+                b"\xff\xff\xff\xffrcon test_password set sv_mapRotation \"gametype war map mp_crash gametype sd map mp_backlot\""
+                    .to_vec()
+            )
+        );
+        assert_eq!(
+            mock_server.read_payload().await,
+            Some(b"\xff\xff\xff\xffrcon test_password set sv_mapRotationCurrent \"\"".to_vec())
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,304 @@
+/*
+ * OLG Server - OnLine Gaming Server Management Tool
+ * Copyright (C) 2025 Luca Cireddu <sardylan@gmail.com>
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::db::{self, DBPool};
+use crate::gamelog::{GameEvents, ServerEvent};
+use crate::models::{GameEvent, PlayerStats};
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+const HEADSHOT: &str = "MOD_HEAD_SHOT";
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+// Turns game events into increments of the per-player totals. Time played
+// needs the join of a player to be matched with its quit or with the end of
+// the map, so open sessions are tracked per server and slot.
+#[derive(Default)]
+pub struct StatsTracker {
+    sessions: HashMap<(String, u8), (String, String, u32)>,
+}
+
+impl StatsTracker {
+    pub fn apply(&mut self, event: &ServerEvent) -> Vec<PlayerStats> {
+        let Some(uptime) = event.uptime else {
+            return Vec::new();
+        };
+        let server_id = event.server_id.clone();
+
+        let mut stats = match &event.event {
+            GameEvent::Join(player) => {
+                let previous = self.sessions.insert(
+                    (server_id, player.slot),
+                    (player.guid.clone(), player.name.clone(), uptime),
+                );
+                previous
+                    .into_iter()
+                    .filter_map(|session| Self::time_played(session, uptime))
+                    .collect()
+            }
+            GameEvent::Quit(player) => self
+                .sessions
+                .remove(&(server_id, player.slot))
+                .and_then(|session| Self::time_played(session, uptime))
+                .into_iter()
+                .collect(),
+            GameEvent::InitGame(_) | GameEvent::ShutdownGame => {
+                let ended = self
+                    .sessions
+                    .keys()
+                    .filter(|(id, _)| *id == server_id)
+                    .cloned()
+                    .collect::<Vec<(String, u8)>>();
+                ended
+                    .into_iter()
+                    .filter_map(|key| self.sessions.remove(&key))
+                    .filter_map(|session| Self::time_played(session, uptime))
+                    .collect()
+            }
+            GameEvent::Kill(hit) => {
+                let mut victim = PlayerStats::new(&hit.victim.guid, &hit.victim.name);
+                victim.deaths = 1;
+                match &hit.attacker {
+                    None => {
+                        victim.suicides = 1;
+                        vec![victim]
+                    }
+                    Some(attacker) if attacker.guid == hit.victim.guid => {
+                        victim.suicides = 1;
+                        vec![victim]
+                    }
+                    Some(attacker) => {
+                        let mut killer = PlayerStats::new(&attacker.guid, &attacker.name);
+                        if !attacker.team.is_empty() && attacker.team == hit.victim.team {
+                            killer.team_kills = 1;
+                        } else {
+                            killer.kills = 1;
+                            killer.damage = i64::from(hit.damage);
+                            killer.headshots = i64::from(hit.means_of_death == HEADSHOT);
+                        }
+                        vec![victim, killer]
+                    }
+                }
+            }
+            GameEvent::Damage(hit) => match &hit.attacker {
+                Some(attacker)
+                    if attacker.guid != hit.victim.guid
+                        && (attacker.team.is_empty() || attacker.team != hit.victim.team) =>
+                {
+                    let mut attacker = PlayerStats::new(&attacker.guid, &attacker.name);
+                    attacker.damage = i64::from(hit.damage);
+                    vec![attacker]
+                }
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+
+        stats.retain(|stats| is_tracked(&stats.guid));
+        stats
+    }
+
+    fn time_played(
+        (guid, name, joined): (String, String, u32),
+        uptime: u32,
+    ) -> Option<PlayerStats> {
+        let seconds = elapsed(joined, uptime)?;
+        let mut stats = PlayerStats::new(&guid, &name);
+        stats.time_played = i64::from(seconds);
+        Some(stats)
+    }
+}
+
+// Seconds between two uptimes of the same server. None when the second one is
+// smaller: the server has been restarted in between.
+pub fn elapsed(since: u32, uptime: u32) -> Option<u32> {
+    uptime.checked_sub(since)
+}
+
+// Bots and clients still connecting have no usable GUID
+pub fn is_tracked(guid: &str) -> bool {
+    !guid.is_empty() && !guid.chars().all(|c| c == '0')
+}
+
+// Only events read from the log are recorded: their position in the file is
+// what makes recording them twice a no-op. Positions older than the
+// retention are forgotten, a log replayed after that is counted again.
+pub fn spawn_recorder(
+    events: &GameEvents,
+    db_pool: DBPool,
+    retention: Duration,
+) -> tokio::task::JoinHandle<()> {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        let mut tracker = StatsTracker::default();
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            let received = tokio::select! {
+                _ = prune.tick() => {
+                    if let Err(error) = prune_lines(&db_pool, retention).await {
+                        warn!("Error pruning recorded log lines: {}", error);
+                    }
+                    continue;
+                }
+                received = receiver.recv() => received,
+            };
+            let event = match received {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Player statistics missed {} game events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let stats = tracker.apply(&event);
+            if stats.is_empty() {
+                continue;
+            }
+            if let Err(error) = record(&db_pool, &event, &stats).await {
+                warn!("Error recording player statistics: {}", error);
+            }
+        }
+    })
+}
+
+async fn prune_lines(db_pool: &DBPool, retention: Duration) -> anyhow::Result<()> {
+    let before = Utc::now() - retention;
+    let pruned = db::prune_player_stats_lines(&db_pool.get().await?, &before).await?;
+    debug!("Pruned {} recorded log lines", pruned);
+    Ok(())
+}
+
+async fn record(
+    db_pool: &DBPool,
+    event: &ServerEvent,
+    stats: &[PlayerStats],
+) -> anyhow::Result<()> {
+    let (Some(log_file), Some(offset), Some(uptime)) =
+        (&event.log_file, event.offset, event.uptime)
+    else {
+        return Ok(());
+    };
+    let line = serde_json::to_string(&(uptime, &event.event))?;
+    let mut db_client = db_pool.get().await?;
+    db::record_player_stats(
+        &mut db_client,
+        &event.server_id,
+        log_file,
+        i64::try_from(offset)?,
+        &line,
+        &event.time,
+        stats,
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GAMES_MP_LOG_FIXTURE, LogEntry};
+
+    fn apply_fixture(tracker: &mut StatsTracker, server_id: &str) -> Vec<PlayerStats> {
+        GAMES_MP_LOG_FIXTURE
+            .lines()
+            .filter_map(|line| line.parse::<LogEntry>().ok())
+            .flat_map(|entry| {
+                tracker.apply(&ServerEvent {
+                    server_id: server_id.to_string(),
+                    time: Utc::now(),
+                    uptime: Some(entry.uptime),
+                    log_file: Some("test".to_string()),
+                    offset: Some(0),
+                    event: entry.event,
+                })
+            })
+            .collect()
+    }
+
+    fn totals(stats: &[PlayerStats], guid: &str) -> PlayerStats {
+        stats.iter().filter(|stats| stats.guid == guid).fold(
+            PlayerStats::new(guid, ""),
+            |mut total, stats| {
+                total.add(stats);
+                total
+            },
+        )
+    }
+
+    #[test]
+    fn test_fixture_totals() {
+        let stats = apply_fixture(&mut StatsTracker::default(), "tdm");
+
+        let sardy = totals(&stats, "0a1b2c3d4e5f60718293a4b5c6d7e8f9");
+        assert_eq!(sardy.kills, 1);
+        assert_eq!(sardy.headshots, 1);
+        assert_eq!(sardy.deaths, 1);
+        assert_eq!(sardy.suicides, 1);
+        assert_eq!(sardy.damage, 165);
+        assert_eq!(sardy.time_played, 602);
+
+        let wolf = totals(&stats, "f9e8d7c6b5a4938271605f4e3d2c1b0a");
+        assert_eq!(wolf.kills, 0);
+        assert_eq!(wolf.deaths, 1);
+        assert_eq!(wolf.damage, 0);
+        assert_eq!(wolf.time_played, 601);
+    }
+
+    #[test]
+    fn test_team_kill() {
+        let line = "  0:31 K;f9e8d7c6b5a4938271605f4e3d2c1b0a;1;allies;Big Bad Wolf;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;allies;^1Sardy^7;m4_mp;135;MOD_HEAD_SHOT;head";
+        let entry = line.parse::<LogEntry>().unwrap();
+        let stats = StatsTracker::default().apply(&ServerEvent {
+            server_id: "tdm".to_string(),
+            time: Utc::now(),
+            uptime: Some(entry.uptime),
+            log_file: Some("test".to_string()),
+            offset: Some(0),
+            event: entry.event,
+        });
+        let sardy = totals(&stats, "0a1b2c3d4e5f60718293a4b5c6d7e8f9");
+        assert_eq!(sardy.team_kills, 1);
+        assert_eq!(sardy.kills, 0);
+        assert_eq!(sardy.headshots, 0);
+    }
+
+    #[test]
+    fn test_sessions_per_server() {
+        let mut tracker = StatsTracker::default();
+        let join = "  0:10 J;0a1b2c3d4e5f60718293a4b5c6d7e8f9;0;^1Sardy^7"
+            .parse::<LogEntry>()
+            .unwrap();
+        let shutdown = "  1:10 ShutdownGame:".parse::<LogEntry>().unwrap();
+        let event = |server_id: &str, entry: &LogEntry| ServerEvent {
+            server_id: server_id.to_string(),
+            time: Utc::now(),
+            uptime: Some(entry.uptime),
+            log_file: Some("test".to_string()),
+            offset: Some(0),
+            event: entry.event.clone(),
+        };
+
+        tracker.apply(&event("tdm", &join));
+        tracker.apply(&event("sd", &join));
+        let stats = tracker.apply(&event("tdm", &shutdown));
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].time_played, 60);
+        assert_eq!(tracker.sessions.len(), 1);
+    }
+}
@@ -15,7 +15,7 @@
  *
  */
 
-pub(crate) fn header() -> () {
+pub(crate) fn header() {
     println!("{} {}", clap::crate_name!(), clap::crate_version!());
     println!();
 }